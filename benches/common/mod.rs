@@ -0,0 +1,46 @@
+//! Input generation and timing shared by the benchmarks.
+
+use std::fmt::Debug;
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+/// How many times each solver runs. The fastest run is reported.
+pub const ITERATIONS: u32 = 20;
+
+/// A xorshift generator with a fixed seed, so that every run benchmarks the same input.
+pub struct Rng {
+    state: u64,
+}
+
+impl Default for Rng {
+    fn default() -> Self {
+        Self {
+            state: 0x2545_f491_4f6c_dd1d,
+        }
+    }
+}
+
+impl Rng {
+    /// A number in `0..bound`.
+    pub fn below(&mut self, bound: u64) -> u64 {
+        self.state ^= self.state << 13;
+        self.state ^= self.state >> 7;
+        self.state ^= self.state << 17;
+
+        self.state % bound
+    }
+}
+
+/// Runs the solver [`ITERATIONS`] times, returning its answer and the fastest time.
+pub fn bench<T, E: Debug>(input: &[u8], solver: fn(&[u8]) -> Result<T, E>) -> (T, Duration) {
+    let answer = solver(input).unwrap();
+    let mut best = Duration::MAX;
+
+    for _ in 0..ITERATIONS {
+        let start = Instant::now();
+        black_box(solver(black_box(input)).unwrap());
+        best = best.min(start.elapsed());
+    }
+
+    (answer, best)
+}
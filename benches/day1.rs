@@ -0,0 +1,157 @@
+//! Compares the day 1 word-at-a-time digit scan against the byte-at-a-time one, and the part 2
+//! matchers against the pattern match part 2 originally used.
+//!
+//! Run with `cargo bench --bench day1`.
+
+mod common;
+
+use std::sync::LazyLock;
+
+use aoc2023::day1::{self, Vocabulary};
+
+use common::Rng;
+
+const LINES: usize = 200_000;
+
+/// Lines of 20 to 100 lowercase letters, with a digit in each half.
+fn generate_input() -> Vec<u8> {
+    let mut rng = Rng::default();
+    let mut next = |bound: u64| rng.below(bound);
+
+    let mut input = Vec::new();
+
+    for _ in 0..LINES {
+        let length = 20 + next(80) as usize;
+        let start = input.len();
+
+        input.extend((0..length).map(|_| b'a' + next(26) as u8));
+
+        let first = next(length as u64 / 2) as usize;
+        let last = length - 1 - next(length as u64 / 2) as usize;
+        input[start + first] = b'0' + next(10) as u8;
+        input[start + last] = b'0' + next(10) as u8;
+
+        input.push(b'\n');
+    }
+
+    input
+}
+
+const WORDS: [&str; 9] = [
+    "one", "two", "three", "four", "five", "six", "seven", "eight", "nine",
+];
+
+/// Lines of 20 to 100 lowercase letters, with three to six digits or English words spread over
+/// them. There is no trailing newline, which the original part 2 did not handle.
+fn generate_words_input() -> Vec<u8> {
+    let mut rng = Rng::default();
+    let mut input = Vec::new();
+
+    for i in 0..LINES {
+        if i > 0 {
+            input.push(b'\n');
+        }
+
+        let length = 20 + rng.below(80) as usize;
+        let mut line = (0..length)
+            .map(|_| b'a' + rng.below(26) as u8)
+            .collect::<Vec<_>>();
+
+        for _ in 0..3 + rng.below(4) {
+            let at = rng.below(line.len() as u64) as usize;
+
+            if rng.below(2) == 0 {
+                line.insert(at, b'1' + rng.below(9) as u8);
+            } else {
+                let word = WORDS[rng.below(9) as usize].as_bytes();
+                line.splice(at..at, word.iter().copied());
+            }
+        }
+
+        input.extend(line);
+    }
+
+    input
+}
+
+/// The part 2 solution before the vocabulary was added, which panics on lines without a digit.
+fn original_part2(input: &[u8]) -> Result<usize, day1::ParseError> {
+    let mut result = 0;
+
+    for line in input.split(|c| *c == b'\n') {
+        let mut index = 0;
+
+        let first = loop {
+            match line[index..] {
+                [num @ b'0'..=b'9', ..] => break (num - b'0') as usize,
+                [b'o', b'n', b'e', ..] => break 1,
+                [b't', b'w', b'o', ..] => break 2,
+                [b't', b'h', b'r', b'e', b'e', ..] => break 3,
+                [b'f', b'o', b'u', b'r', ..] => break 4,
+                [b'f', b'i', b'v', b'e', ..] => break 5,
+                [b's', b'i', b'x', ..] => break 6,
+                [b's', b'e', b'v', b'e', b'n', ..] => break 7,
+                [b'e', b'i', b'g', b'h', b't', ..] => break 8,
+                [b'n', b'i', b'n', b'e', ..] => break 9,
+                _ => {}
+            }
+
+            index += 1;
+        };
+
+        index = line.len();
+
+        let second = loop {
+            match line[..index] {
+                [.., num @ b'0'..=b'9'] => break (num - b'0') as usize,
+                [.., b'o', b'n', b'e'] => break 1,
+                [.., b't', b'w', b'o'] => break 2,
+                [.., b't', b'h', b'r', b'e', b'e'] => break 3,
+                [.., b'f', b'o', b'u', b'r'] => break 4,
+                [.., b'f', b'i', b'v', b'e'] => break 5,
+                [.., b's', b'i', b'x'] => break 6,
+                [.., b's', b'e', b'v', b'e', b'n'] => break 7,
+                [.., b'e', b'i', b'g', b'h', b't'] => break 8,
+                [.., b'n', b'i', b'n', b'e'] => break 9,
+                _ => {}
+            }
+
+            index -= 1;
+        };
+
+        result += first * 10 + second;
+    }
+
+    Ok(result)
+}
+
+fn vocabulary_part2(input: &[u8]) -> Result<usize, day1::ParseError> {
+    static ENGLISH: LazyLock<Vocabulary> = LazyLock::new(Vocabulary::english);
+
+    day1::calibration_sum(input, &ENGLISH)
+}
+
+fn bench(name: &str, input: &[u8], solver: fn(&[u8]) -> Result<usize, day1::ParseError>) -> usize {
+    let (answer, best) = common::bench(input, solver);
+
+    println!(
+        "{name:<20} {:>10.2?} {:>8.2} GB/s",
+        best,
+        input.len() as f64 / best.as_secs_f64() / 1e9
+    );
+
+    answer
+}
+
+fn main() {
+    let input = generate_input();
+
+    let part1 = bench("part1 (words)", &input, day1::part1);
+    assert_eq!(part1, bench("part1 (scalar)", &input, day1::part1_scalar));
+
+    let input = generate_words_input();
+
+    let part2 = bench("part2 (english)", &input, day1::part2);
+    assert_eq!(part2, bench("part2 (vocabulary)", &input, vocabulary_part2));
+    assert_eq!(part2, bench("part2 (original)", &input, original_part2));
+}
@@ -0,0 +1,48 @@
+//! Compares the day 7 hand classifier against the original const-evaluated table.
+//!
+//! Run with `cargo bench --features hand-table --bench day7`.
+
+mod common;
+
+use aoc2023::day7;
+
+use common::Rng;
+
+const HANDS: usize = 100_000;
+
+fn generate_input() -> Vec<u8> {
+    let mut rng = Rng::default();
+    let mut input = Vec::new();
+
+    for _ in 0..HANDS {
+        for _ in 0..5 {
+            input.push(b"23456789TJQKA"[rng.below(13) as usize]);
+        }
+
+        input.extend_from_slice(format!(" {}\n", rng.below(1000) + 1).as_bytes());
+    }
+
+    input
+}
+
+fn bench(name: &str, input: &[u8], solver: fn(&[u8]) -> Result<i64, day7::ParseError>) -> i64 {
+    let (answer, best) = common::bench(input, solver);
+
+    println!(
+        "{name:<20} {:>10.2?} {:>8.1} ns/hand",
+        best,
+        best.as_nanos() as f64 / HANDS as f64
+    );
+
+    answer
+}
+
+fn main() {
+    let input = generate_input();
+
+    let part1 = bench("part1 (classifier)", &input, day7::part1);
+    assert_eq!(part1, bench("part1 (table)", &input, day7::table::part1));
+
+    let part2 = bench("part2 (classifier)", &input, day7::part2);
+    assert_eq!(part2, bench("part2 (table)", &input, day7::table::part2));
+}
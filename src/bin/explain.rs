@@ -0,0 +1,22 @@
+//! Prints how every line of the day 1 input is read by both parts.
+//!
+//! Run with `cargo run --bin explain [path]`, which defaults to `input/2023/day1.txt`.
+
+use std::process::ExitCode;
+
+fn main() -> ExitCode {
+    let path = std::env::args()
+        .nth(1)
+        .unwrap_or_else(|| "input/2023/day1.txt".to_owned());
+
+    match std::fs::read(&path) {
+        Ok(input) => {
+            print!("{}", aoc2023::day1::explain(&input));
+            ExitCode::SUCCESS
+        }
+        Err(e) => {
+            eprintln!("cannot read {path}: {e}");
+            ExitCode::FAILURE
+        }
+    }
+}
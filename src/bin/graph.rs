@@ -0,0 +1,38 @@
+//! Prints the day 3 symbols and the numbers next to them, as JSON or Graphviz DOT.
+//!
+//! Run with `cargo run --bin graph [path] [dot]`, which defaults to `input/2023/day3.txt` and
+//! JSON.
+
+use std::process::ExitCode;
+
+use aoc2023::day3::graph::Graph;
+
+fn main() -> ExitCode {
+    let mut args = std::env::args().skip(1);
+    let path = args
+        .next()
+        .unwrap_or_else(|| "input/2023/day3.txt".to_owned());
+
+    let input = match std::fs::read(&path) {
+        Ok(input) => input,
+        Err(e) => {
+            eprintln!("cannot read {path}: {e}");
+            return ExitCode::FAILURE;
+        }
+    };
+
+    let graph = match Graph::new(&input) {
+        Ok(graph) => graph,
+        Err(e) => {
+            eprintln!("invalid schematic in {path}: {e}");
+            return ExitCode::FAILURE;
+        }
+    };
+
+    match args.next().as_deref() {
+        Some("dot") => print!("{}", graph.to_dot()),
+        _ => print!("{}", graph.to_json()),
+    }
+
+    ExitCode::SUCCESS
+}
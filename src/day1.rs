@@ -1,109 +1,405 @@
+use std::error::Error;
+use std::fmt;
+use std::sync::LazyLock;
+
+use bstr::{BStr, ByteSlice};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ParseError {
+    /// The line has no token to read a calibration value from.
+    NoDigit { line: usize },
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::NoDigit { line } => write!(f, "line {line}: no digit found"),
+        }
+    }
+}
+
+impl Error for ParseError {}
+
 #[aoc(day1, part1)]
-pub fn part1(input: &[u8]) -> i32 {
+pub fn part1(input: &[u8]) -> Result<usize, ParseError> {
+    lines(input)
+        .map(|(line_number, line)| {
+            let (Some(first), Some(second)) = (first_digit(line), last_digit(line)) else {
+                return Err(ParseError::NoDigit { line: line_number });
+            };
+
+            Ok((first - b'0') as usize * 10 + (second - b'0') as usize)
+        })
+        .sum()
+}
+
+/// Like [`part1`], but looks at one byte at a time.
+#[aoc(day1, part1, Scalar)]
+pub fn part1_scalar(input: &[u8]) -> Result<usize, ParseError> {
     input
-        .split(|b| *b == b'\n')
-        .map(|row| {
-            let first = row.iter().find(|c| c.is_ascii_digit()).unwrap() - b'0';
-            let second = row.iter().rfind(|c| c.is_ascii_digit()).unwrap() - b'0';
+        .split(|c| *c == b'\n')
+        .enumerate()
+        .map(|(i, line)| (i + 1, line.strip_suffix(b"\r").unwrap_or(line)))
+        .filter(|(_, line)| !line.trim_ascii().is_empty())
+        .map(|(line_number, line)| {
+            let first = line.iter().find(|c| c.is_ascii_digit());
+            let second = line.iter().rfind(|c| c.is_ascii_digit());
 
-            first as i32 * 10 + second as i32
+            let (Some(first), Some(second)) = (first, second) else {
+                return Err(ParseError::NoDigit { line: line_number });
+            };
+
+            Ok((first - b'0') as usize * 10 + (second - b'0') as usize)
         })
         .sum()
 }
 
+const ONES: u64 = u64::MAX / 255;
+
+/// Sets the highest bit of every byte of the word that is an ASCII digit, and clears every other
+/// bit. No carry crosses from one byte to the next, so every byte is tested exactly.
+fn digit_mask(word: u64) -> u64 {
+    let low = word & (ONES * 0x7f);
+    let below_end = ONES * (0x7f + b'9' as u64 + 1) - low;
+    let above_start = low + ONES * (0x7f - (b'0' as u64 - 1));
+
+    below_end & above_start & !word & (ONES * 0x80)
+}
+
+/// The first ASCII digit of the line, looking at eight bytes at a time.
+fn first_digit(line: &[u8]) -> Option<u8> {
+    let mut chunks = line.chunks_exact(8);
+
+    for chunk in chunks.by_ref() {
+        let mask = digit_mask(u64::from_le_bytes(chunk.try_into().unwrap()));
+
+        if mask != 0 {
+            return Some(chunk[mask.trailing_zeros() as usize / 8]);
+        }
+    }
+
+    chunks
+        .remainder()
+        .iter()
+        .find(|c| c.is_ascii_digit())
+        .copied()
+}
+
+/// The last ASCII digit of the line, looking at eight bytes at a time.
+fn last_digit(line: &[u8]) -> Option<u8> {
+    let mut chunks = line.rchunks_exact(8);
+
+    for chunk in chunks.by_ref() {
+        let mask = digit_mask(u64::from_le_bytes(chunk.try_into().unwrap()));
+
+        if mask != 0 {
+            return Some(chunk[7 - mask.leading_zeros() as usize / 8]);
+        }
+    }
+
+    chunks
+        .remainder()
+        .iter()
+        .rfind(|c| c.is_ascii_digit())
+        .copied()
+}
+
+/// Reads the built-in English vocabulary with a hand-written matcher, which is faster than the
+/// general [`Vocabulary`] lookup that [`calibration_sum`] uses.
 #[aoc(day1, part2)]
-pub fn part2(input: &[u8]) -> usize {
-    let mut result = 0;
+pub fn part2(input: &[u8]) -> Result<usize, ParseError> {
+    lines(input)
+        .map(|(line_number, line)| {
+            let first = (0..line.len()).find_map(|start| english_prefix(&line[start..]));
+            let last = (1..=line.len())
+                .rev()
+                .find_map(|end| english_suffix(&line[..end]));
 
-    for line in input.split(|c| *c == b'\n') {
-        let mut index = 0;
+            let (Some(first), Some(last)) = (first, last) else {
+                return Err(ParseError::NoDigit { line: line_number });
+            };
 
-        let first = loop {
-            match line[index..] {
-                [num @ b'0'..=b'9', ..] => {
-                    break (num - b'0') as usize;
-                }
-                [b'o', b'n', b'e', ..] => {
-                    break 1;
-                }
-                [b't', b'w', b'o', ..] => {
-                    break 2;
-                }
-                [b't', b'h', b'r', b'e', b'e', ..] => {
-                    break 3;
-                }
-                [b'f', b'o', b'u', b'r', ..] => {
-                    break 4;
-                }
-                [b'f', b'i', b'v', b'e', ..] => {
-                    break 5;
-                }
-                [b's', b'i', b'x', ..] => {
-                    break 6;
-                }
-                [b's', b'e', b'v', b'e', b'n', ..] => {
-                    break 7;
-                }
-                [b'e', b'i', b'g', b'h', b't', ..] => {
-                    break 8;
-                }
-                [b'n', b'i', b'n', b'e', ..] => {
-                    break 9;
+            Ok(first as usize * 10 + last as usize)
+        })
+        .sum()
+}
+
+/// The value of the digit or English word the bytes start with.
+fn english_prefix(bytes: &[u8]) -> Option<u8> {
+    match bytes {
+        [digit @ b'0'..=b'9', ..] => Some(digit - b'0'),
+        [b'o', b'n', b'e', ..] => Some(1),
+        [b't', b'w', b'o', ..] => Some(2),
+        [b't', b'h', b'r', b'e', b'e', ..] => Some(3),
+        [b'f', b'o', b'u', b'r', ..] => Some(4),
+        [b'f', b'i', b'v', b'e', ..] => Some(5),
+        [b's', b'i', b'x', ..] => Some(6),
+        [b's', b'e', b'v', b'e', b'n', ..] => Some(7),
+        [b'e', b'i', b'g', b'h', b't', ..] => Some(8),
+        [b'n', b'i', b'n', b'e', ..] => Some(9),
+        _ => None,
+    }
+}
+
+/// The value of the digit or English word the bytes end with.
+fn english_suffix(bytes: &[u8]) -> Option<u8> {
+    match bytes {
+        [.., digit @ b'0'..=b'9'] => Some(digit - b'0'),
+        [.., b'o', b'n', b'e'] => Some(1),
+        [.., b't', b'w', b'o'] => Some(2),
+        [.., b't', b'h', b'r', b'e', b'e'] => Some(3),
+        [.., b'f', b'o', b'u', b'r'] => Some(4),
+        [.., b'f', b'i', b'v', b'e'] => Some(5),
+        [.., b's', b'i', b'x'] => Some(6),
+        [.., b's', b'e', b'v', b'e', b'n'] => Some(7),
+        [.., b'e', b'i', b'g', b'h', b't'] => Some(8),
+        [.., b'n', b'i', b'n', b'e'] => Some(9),
+        _ => None,
+    }
+}
+
+static DIGIT_TOKENS: LazyLock<Vocabulary> = LazyLock::new(Vocabulary::digits);
+static ENGLISH_TOKENS: LazyLock<Vocabulary> = LazyLock::new(Vocabulary::english);
+
+/// The sum of the calibration values of every line, reading tokens from the given vocabulary.
+pub fn calibration_sum(input: &[u8], vocabulary: &Vocabulary) -> Result<usize, ParseError> {
+    lines(input)
+        .map(|(line_number, line)| {
+            let (Some(first), Some(last)) = (vocabulary.first(line), vocabulary.last(line)) else {
+                return Err(ParseError::NoDigit { line: line_number });
+            };
+
+            Ok(first.value as usize * 10 + last.value as usize)
+        })
+        .sum()
+}
+
+/// How the calibration value of every line is read under the rules of both parts.
+pub fn explain(input: &[u8]) -> Explanation<'_> {
+    let lines = lines(input)
+        .map(|(line_number, line)| LineExplanation {
+            line: line_number,
+            part1: Reading::new(line, &DIGIT_TOKENS),
+            part2: Reading::new(line, &ENGLISH_TOKENS),
+        })
+        .collect();
+
+    Explanation { lines }
+}
+
+/// The explanation of every line of an input, which displays as a table.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Explanation<'a> {
+    pub lines: Vec<LineExplanation<'a>>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LineExplanation<'a> {
+    pub line: usize,
+    /// The reading under the rules of part 1, or `None` if the line has no digit.
+    pub part1: Option<Reading<'a>>,
+    /// The reading under the rules of part 2, or `None` if the line has no digit or digit word.
+    pub part2: Option<Reading<'a>>,
+}
+
+/// The first and last tokens of a line, and the calibration value they make.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Reading<'a> {
+    pub first: Token<'a>,
+    pub last: Token<'a>,
+    pub value: usize,
+}
+
+impl<'a> Reading<'a> {
+    fn new(line: &'a [u8], vocabulary: &Vocabulary) -> Option<Self> {
+        let first = Token::new(line, vocabulary.first(line)?);
+        let last = Token::new(line, vocabulary.last(line)?);
+
+        Some(Self {
+            first,
+            last,
+            value: first.value as usize * 10 + last.value as usize,
+        })
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Token<'a> {
+    pub text: &'a BStr,
+    /// The byte offset of the token in its line.
+    pub offset: usize,
+    pub is_word: bool,
+    pub value: u8,
+}
+
+impl<'a> Token<'a> {
+    fn new(line: &'a [u8], found: Match) -> Self {
+        let text = &line[found.start..found.end];
+
+        Self {
+            text: text.as_bstr(),
+            offset: found.start,
+            is_word: !matches!(text, [b'0'..=b'9']),
+            value: found.value,
+        }
+    }
+}
+
+impl fmt::Display for Token<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let kind = if self.is_word { "word" } else { "digit" };
+        let token = format!("{} {kind} @{}", self.text, self.offset);
+
+        f.pad(&token)
+    }
+}
+
+impl fmt::Display for Explanation<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "{:>6} | {:<16} | {:<16} | {:>5} | {:<16} | {:<16} | {:>5}",
+            "line", "part 1 first", "part 1 last", "value", "part 2 first", "part 2 last", "value"
+        )?;
+
+        for line in &self.lines {
+            write!(f, "{:>6}", line.line)?;
+
+            for reading in [line.part1, line.part2] {
+                match reading {
+                    Some(Reading { first, last, value }) => {
+                        write!(f, " | {first:<16} | {last:<16} | {value:>5}")?
+                    }
+                    None => write!(f, " | {:<16} | {:<16} | {:>5}", "-", "-", "-")?,
                 }
-                _ => {}
             }
 
-            index += 1;
-        };
+            // Point out the lines where the two parts read different values.
+            if line.part1.map(|r| r.value) != line.part2.map(|r| r.value) {
+                write!(f, " *")?;
+            }
 
-        index = line.len();
+            writeln!(f)?;
+        }
 
-        let second = loop {
-            match line[..index] {
-                [.., num @ b'0'..=b'9'] => {
-                    break (num - b'0') as usize;
-                }
-                [.., b'o', b'n', b'e'] => {
-                    break 1;
-                }
-                [.., b't', b'w', b'o'] => {
-                    break 2;
-                }
-                [.., b't', b'h', b'r', b'e', b'e'] => {
-                    break 3;
-                }
-                [.., b'f', b'o', b'u', b'r'] => {
-                    break 4;
-                }
-                [.., b'f', b'i', b'v', b'e'] => {
-                    break 5;
-                }
-                [.., b's', b'i', b'x'] => {
-                    break 6;
-                }
-                [.., b's', b'e', b'v', b'e', b'n'] => {
-                    break 7;
-                }
-                [.., b'e', b'i', b'g', b'h', b't'] => {
-                    break 8;
-                }
-                [.., b'n', b'i', b'n', b'e'] => {
-                    break 9;
+        Ok(())
+    }
+}
+
+/// The non-blank lines of the input along with their line numbers, without any `\r` before the
+/// line break. The line breaks are found with `memchr`.
+fn lines(input: &[u8]) -> impl Iterator<Item = (usize, &[u8])> {
+    ByteSlice::lines(input)
+        .enumerate()
+        .map(|(i, line)| (i + 1, line))
+        .filter(|(_, line)| !line.trim_ascii().is_empty())
+}
+
+/// A token found in a line.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Match {
+    pub start: usize,
+    pub end: usize,
+    pub value: u8,
+}
+
+/// A set of tokens standing for digits, such as `"7"` or `"seven"`.
+///
+/// Tokens may overlap, as in `eightwo`: the first token is the one that starts first and the last
+/// token is the one that ends last. When several tokens start (or end) at the same place, the
+/// shortest one is used.
+#[derive(Debug, Clone)]
+pub struct Vocabulary {
+    /// The tokens and their values grouped by their first byte, shortest first.
+    by_first: Vec<Vec<(Box<[u8]>, u8)>>,
+    /// The tokens and their values grouped by their last byte, shortest first.
+    by_last: Vec<Vec<(Box<[u8]>, u8)>>,
+}
+
+impl Vocabulary {
+    /// Builds a vocabulary from tokens and the digit each one stands for.
+    ///
+    /// # Panics
+    ///
+    /// Panics if a token is empty.
+    pub fn new<'a>(tokens: impl IntoIterator<Item = (&'a str, u8)>) -> Self {
+        let mut by_first = vec![Vec::new(); 256];
+        let mut by_last = vec![Vec::new(); 256];
+
+        for (token, value) in tokens {
+            let token = token.as_bytes();
+            let (Some(first), Some(last)) = (token.first(), token.last()) else {
+                panic!("tokens must not be empty");
+            };
+
+            by_first[*first as usize].push((token.into(), value));
+            by_last[*last as usize].push((token.into(), value));
+        }
+
+        for group in by_first.iter_mut().chain(&mut by_last) {
+            group.sort_by_key(|(token, _): &(Box<[u8]>, u8)| token.len());
+        }
+
+        Self { by_first, by_last }
+    }
+
+    /// The ASCII digits `0` to `9`.
+    pub fn digits() -> Self {
+        Self::new(DIGITS.into_iter().zip(0..))
+    }
+
+    /// The ASCII digits along with the English words `one` to `nine`.
+    pub fn english() -> Self {
+        Self::new(
+            DIGITS
+                .into_iter()
+                .zip(0..)
+                .chain(ENGLISH.into_iter().zip(1..)),
+        )
+    }
+
+    pub fn first(&self, line: &[u8]) -> Option<Match> {
+        for (start, byte) in line.iter().enumerate() {
+            for (token, value) in &self.by_first[*byte as usize] {
+                if line[start..].starts_with(token) {
+                    return Some(Match {
+                        start,
+                        end: start + token.len(),
+                        value: *value,
+                    });
                 }
-                _ => {}
             }
+        }
 
-            index -= 1;
-        };
-
-        result += first * 10 + second;
+        None
     }
 
-    result
+    pub fn last(&self, line: &[u8]) -> Option<Match> {
+        for (end, byte) in line.iter().enumerate().rev() {
+            for (token, value) in &self.by_last[*byte as usize] {
+                if line[..=end].ends_with(token) {
+                    return Some(Match {
+                        start: end + 1 - token.len(),
+                        end: end + 1,
+                        value: *value,
+                    });
+                }
+            }
+        }
+
+        None
+    }
 }
 
+const DIGITS: [&str; 10] = ["0", "1", "2", "3", "4", "5", "6", "7", "8", "9"];
+const ENGLISH: [&str; 9] = [
+    "one", "two", "three", "four", "five", "six", "seven", "eight", "nine",
+];
+
 #[cfg(test)]
 mod tests {
+    use super::{ParseError, Vocabulary};
+
     const TEST_1: &str = "1abc2
 pqr3stu8vwx
 a1b2c3d4e5f
@@ -119,11 +415,184 @@ zoneight234
 
     #[test]
     fn test_1() {
-        assert_eq!(super::part1(TEST_1.as_bytes()), 142);
+        assert_eq!(super::part1(TEST_1.as_bytes()).unwrap(), 142);
     }
 
     #[test]
     fn test_2() {
-        assert_eq!(super::part2(TEST_2.as_bytes()), 281);
+        assert_eq!(super::part2(TEST_2.as_bytes()).unwrap(), 281);
+    }
+
+    #[test]
+    fn test_overlaps() {
+        let vocabulary = Vocabulary::english();
+
+        let first = vocabulary.first(b"xeightwox").unwrap();
+        let last = vocabulary.last(b"xeightwox").unwrap();
+        assert_eq!((first.start, first.end, first.value), (1, 6, 8));
+        assert_eq!((last.start, last.end, last.value), (5, 8, 2));
+
+        assert_eq!(
+            super::part2(b"eightwo\ntwone\noneight\nsevenine").unwrap(),
+            82 + 21 + 18 + 79
+        );
+        assert_eq!(vocabulary.first(b"abc"), None);
+        assert_eq!(vocabulary.last(b""), None);
+    }
+
+    #[test]
+    fn test_vocabulary() {
+        let french = Vocabulary::new([
+            ("zero", 0),
+            ("un", 1),
+            ("deux", 2),
+            ("trois", 3),
+            ("quatre", 4),
+            ("cinq", 5),
+            ("six", 6),
+            ("sept", 7),
+            ("huit", 8),
+            ("neuf", 9),
+        ]);
+
+        assert_eq!(
+            super::calibration_sum(b"deuxzeroneuf\nxtroisix\nquatrept", &french).unwrap(),
+            29 + 36 + 44
+        );
+
+        // Tokens that contain each other still resolve to the first start and the last end.
+        let nested = Vocabulary::new([("abcdef", 1), ("bc", 2), ("cde", 3), ("f", 4)]);
+        assert_eq!(nested.first(b"xabcdefx").unwrap().value, 1);
+        assert_eq!(nested.last(b"xabcdefx").unwrap().value, 4);
+        assert_eq!(nested.first(b"xabcdex").unwrap().value, 2);
+        assert_eq!(nested.last(b"xabcdex").unwrap().value, 3);
+
+        assert_eq!(
+            super::calibration_sum(TEST_1.as_bytes(), &Vocabulary::digits()).unwrap(),
+            142
+        );
+    }
+
+    #[test]
+    fn test_english_matcher() {
+        let english = Vocabulary::english();
+        let words = [
+            "one", "two", "three", "four", "five", "six", "seven", "eight", "nine",
+        ];
+
+        // Every pair of overlapping or adjacent words, with digits and other letters around them.
+        for a in words {
+            for b in words {
+                for line in [
+                    format!("{a}{b}"),
+                    format!("x{a}{}", &b[1..]),
+                    format!("{}{b}x", &a[..a.len() - 1]),
+                    format!("3{a}x{b}0"),
+                ] {
+                    assert_eq!(
+                        super::part2(line.as_bytes()),
+                        super::calibration_sum(line.as_bytes(), &english),
+                        "{line}"
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_line_endings() {
+        let crlf = TEST_2.replace('\n', "\r\n") + "\r\n";
+        assert_eq!(super::part2(crlf.as_bytes()).unwrap(), 281);
+
+        let blank = format!("\n{TEST_1}\n\n  \n");
+        assert_eq!(super::part1(blank.as_bytes()).unwrap(), 142);
+        assert_eq!(super::part1(b"").unwrap(), 0);
+    }
+
+    #[test]
+    fn test_no_digit() {
+        let input = format!("{TEST_1}\n\njust letters");
+        assert_eq!(
+            super::part1(input.as_bytes()),
+            Err(ParseError::NoDigit { line: 6 })
+        );
+        assert_eq!(
+            super::part2(input.as_bytes()),
+            Err(ParseError::NoDigit { line: 6 })
+        );
+        assert_eq!(
+            super::part2(b"abc\none"),
+            Err(ParseError::NoDigit { line: 1 })
+        );
+    }
+
+    #[test]
+    fn test_digit_scan() {
+        let bytes = (0..=255u8).collect::<Vec<_>>();
+
+        // Every byte value, at every position of a word and of the remainder.
+        for byte in 0..=255u8 {
+            for length in 1..20 {
+                for position in 0..length {
+                    let mut line = vec![b'x'; length];
+                    line[position] = byte;
+
+                    let expected = byte.is_ascii_digit().then_some(byte);
+                    assert_eq!(super::first_digit(&line), expected);
+                    assert_eq!(super::last_digit(&line), expected);
+                }
+            }
+        }
+
+        assert_eq!(super::first_digit(&bytes), Some(b'0'));
+        assert_eq!(super::last_digit(&bytes), Some(b'9'));
+        assert_eq!(super::first_digit(b"ab1cdefgh2ijklmnop3q"), Some(b'1'));
+        assert_eq!(super::last_digit(b"ab1cdefgh2ijklmnop3q"), Some(b'3'));
+        assert_eq!(super::last_digit(b"a1cdefgh2ijklmnopqrs"), Some(b'2'));
+        assert_eq!(super::first_digit(b""), None);
+
+        let input = format!("{TEST_1}\r\n\r\n\u{e9}9\u{ff}x\u{b9}");
+        assert_eq!(
+            super::part1(input.as_bytes()),
+            super::part1_scalar(input.as_bytes())
+        );
+    }
+
+    #[test]
+    fn test_explain() {
+        let explanation = super::explain(b"two1nine\n\nabc\n4nineeightseven2");
+        let lines = &explanation.lines;
+
+        assert_eq!(lines.len(), 3);
+        assert_eq!(lines.iter().map(|l| l.line).collect::<Vec<_>>(), [1, 3, 4]);
+
+        let first = lines[0].part1.unwrap();
+        assert_eq!(
+            (first.first.offset, first.last.offset, first.value),
+            (3, 3, 11)
+        );
+        assert!(!first.first.is_word);
+
+        let first = lines[0].part2.unwrap();
+        assert_eq!(first.first.text, "two");
+        assert_eq!(first.last.text, "nine");
+        assert_eq!(
+            (first.first.offset, first.last.offset, first.value),
+            (0, 4, 29)
+        );
+        assert!(first.first.is_word && first.last.is_word);
+
+        assert_eq!((lines[1].part1, lines[1].part2), (None, None));
+        assert_eq!(lines[2].part1.map(|r| r.value), Some(42));
+        assert_eq!(lines[2].part2.map(|r| r.value), Some(42));
+
+        let table = explanation.to_string();
+        let rows = table.lines().collect::<Vec<_>>();
+
+        assert_eq!(rows.len(), 4);
+        assert!(rows[1].starts_with("     1 | 1 digit @3       | 1 digit @3       |    11 |"));
+        assert!(rows[1].ends_with("nine word @4     |    29 *"));
+        assert!(rows[2].contains("| -                |"));
+        assert!(!rows[3].ends_with('*'));
     }
 }
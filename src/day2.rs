@@ -1,91 +1,228 @@
-#[aoc(day2, part1)]
-pub fn part1(input: &str) -> usize {
-    let mut result = 0;
-
-    'games: for (i, line) in input.split('\n').enumerate() {
-        let i = i + 1;
-        let prefix_width = match i {
-            1..=9 => 8,
-            10..=99 => 9,
-            100..=1000 => 10,
-            _ => unreachable!(),
-        };
-
-        let line = &line[prefix_width..];
-
-        for subset in line.split(';') {
-            let mut red = 0;
-            let mut green = 0;
-            let mut blue = 0;
-
-            for cube_selection in subset.split(',') {
-                let (count, color) = cube_selection.trim().split_once(' ').unwrap();
-                let count = count.parse::<usize>().unwrap();
-
-                let (color, threshold) = match color {
-                    "red" => (&mut red, 12),
-                    "green" => (&mut green, 13),
-                    "blue" => (&mut blue, 14),
-                    _ => unreachable!(),
-                };
-
-                *color += count;
-
-                if *color > threshold {
-                    continue 'games;
-                }
+use std::error::Error;
+use std::fmt;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ParseError {
+    /// The line does not start with `Game <id>:`.
+    MissingPrefix {
+        line: usize,
+    },
+    InvalidId {
+        line: usize,
+    },
+    /// A cube selection is not a count followed by a colour.
+    InvalidCubes {
+        line: usize,
+    },
+    UnknownColor {
+        line: usize,
+        color: String,
+    },
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::MissingPrefix { line } => write!(f, "line {line}: expected `Game <id>:`"),
+            Self::InvalidId { line } => write!(f, "line {line}: invalid game id"),
+            Self::InvalidCubes { line } => {
+                write!(f, "line {line}: expected a count followed by a colour")
+            }
+            Self::UnknownColor { line, color } => {
+                write!(f, "line {line}: unknown colour {color:?}")
             }
         }
+    }
+}
+
+impl Error for ParseError {}
+
+/// The colours of the cubes a game can draw, in the order their counts are stored.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Colors<'a> {
+    names: &'a [&'a str],
+}
+
+impl<'a> Colors<'a> {
+    pub const STANDARD: Colors<'static> = Colors {
+        names: &["red", "green", "blue"],
+    };
+
+    pub const fn new(names: &'a [&'a str]) -> Self {
+        Self { names }
+    }
+
+    pub fn names(&self) -> &'a [&'a str] {
+        self.names
+    }
+
+    fn index(&self, name: &str) -> Option<usize> {
+        self.names.iter().position(|n| *n == name)
+    }
+
+    /// A bag holding the given number of cubes of each colour, and none of the colours that are
+    /// not listed. Returns `None` if a colour is not part of this set.
+    pub fn bag(&self, limits: &[(&str, usize)]) -> Option<Draw> {
+        let mut bag = Draw::empty(self);
+
+        for (name, limit) in limits {
+            bag.counts[self.index(name)?] = *limit;
+        }
 
-        result += i;
+        Some(bag)
     }
+}
 
-    result
+/// The cubes shown in one draw, or the cubes in a bag, counted per colour of a [`Colors`] set.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Draw {
+    pub counts: Vec<usize>,
 }
 
-#[aoc(day2, part2)]
-pub fn part2(input: &str) -> usize {
-    let mut result = 0;
-
-    for (i, line) in input.split('\n').enumerate() {
-        let i = i + 1;
-        let prefix_width = match i {
-            1..=9 => 8,
-            10..=99 => 9,
-            100..=1000 => 10,
-            _ => unreachable!(),
-        };
-
-        let line = &line[prefix_width..];
-
-        let mut red = 0;
-        let mut green = 0;
-        let mut blue = 0;
-
-        for subset in line.split(';') {
-            for cube_selection in subset.split(',') {
-                let (count, color) = cube_selection.trim().split_once(' ').unwrap();
-                let count = count.parse::<usize>().unwrap();
-
-                let color = match color {
-                    "red" => &mut red,
-                    "green" => &mut green,
-                    "blue" => &mut blue,
-                    _ => unreachable!(),
-                };
-
-                *color = usize::max(*color, count);
+impl Draw {
+    pub fn empty(colors: &Colors) -> Self {
+        Self {
+            counts: vec![0; colors.names.len()],
+        }
+    }
+
+    /// Whether a bag with these cubes could have produced the given draw.
+    pub fn contains(&self, draw: &Draw) -> bool {
+        self.counts.iter().zip(&draw.counts).all(|(a, b)| b <= a)
+    }
+
+    pub fn power(&self) -> usize {
+        self.counts.iter().product()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Game {
+    pub id: usize,
+    pub draws: Vec<Draw>,
+}
+
+impl Game {
+    pub fn parse(line_number: usize, line: &str, colors: &Colors) -> Result<Self, ParseError> {
+        let (id, draws) = line
+            .strip_prefix("Game ")
+            .and_then(|line| line.split_once(':'))
+            .ok_or(ParseError::MissingPrefix { line: line_number })?;
+
+        let id = id
+            .parse()
+            .map_err(|_| ParseError::InvalidId { line: line_number })?;
+
+        let draws = draws
+            .split(';')
+            .map(|subset| parse_draw(line_number, subset, colors))
+            .collect::<Result<_, _>>()?;
+
+        Ok(Self { id, draws })
+    }
+
+    /// The smallest bag that could have produced every draw of the game.
+    pub fn minimal_bag(&self) -> Draw {
+        let mut draws = self.draws.iter();
+        let mut bag = draws.next().cloned().unwrap_or(Draw { counts: Vec::new() });
+
+        for draw in draws {
+            for (max, count) in bag.counts.iter_mut().zip(&draw.counts) {
+                *max = usize::max(*max, *count);
             }
         }
 
-        result += red * green * blue;
+        bag
     }
+}
+
+fn parse_draw(line_number: usize, subset: &str, colors: &Colors) -> Result<Draw, ParseError> {
+    let mut draw = Draw::empty(colors);
+
+    for cube_selection in subset.split(',') {
+        let (count, color) = cube_selection
+            .trim()
+            .split_once(' ')
+            .ok_or(ParseError::InvalidCubes { line: line_number })?;
+
+        let count = count
+            .parse::<usize>()
+            .map_err(|_| ParseError::InvalidCubes { line: line_number })?;
+
+        let index = colors
+            .index(color)
+            .ok_or_else(|| ParseError::UnknownColor {
+                line: line_number,
+                color: color.to_owned(),
+            })?;
 
-    result
+        draw.counts[index] += count;
+    }
+
+    Ok(draw)
+}
+
+/// Parses every game, ignoring trailing blank lines. Line numbers in errors start at 1.
+pub fn parse_games<'a>(
+    input: &'a str,
+    colors: &'a Colors,
+) -> impl Iterator<Item = Result<Game, ParseError>> + 'a {
+    input
+        .trim_end()
+        .lines()
+        .enumerate()
+        .map(|(i, line)| Game::parse(i + 1, line, colors))
+}
+
+/// The sum of the ids of the games each bag could have produced, checking every bag in a single
+/// pass over the games.
+pub fn possible_games(
+    input: &str,
+    colors: &Colors,
+    bags: &[Draw],
+) -> Result<Vec<usize>, ParseError> {
+    let mut sums = vec![0; bags.len()];
+
+    for game in parse_games(input, colors) {
+        let game = game?;
+        let needed = game.minimal_bag();
+
+        for (sum, bag) in sums.iter_mut().zip(bags) {
+            if bag.contains(&needed) {
+                *sum += game.id;
+            }
+        }
+    }
+
+    Ok(sums)
+}
+
+/// The sum of the powers of the minimal bag of every game.
+pub fn total_power(input: &str, colors: &Colors) -> Result<usize, ParseError> {
+    parse_games(input, colors)
+        .map(|game| Ok(game?.minimal_bag().power()))
+        .sum()
+}
+
+#[aoc(day2, part1)]
+pub fn part1(input: &str) -> Result<usize, ParseError> {
+    let colors = Colors::STANDARD;
+    let bag = colors
+        .bag(&[("red", 12), ("green", 13), ("blue", 14)])
+        .unwrap();
+
+    Ok(possible_games(input, &colors, &[bag])?[0])
+}
+
+#[aoc(day2, part2)]
+pub fn part2(input: &str) -> Result<usize, ParseError> {
+    total_power(input, &Colors::STANDARD)
 }
 
 #[cfg(test)]
 mod tests {
+    use super::{Colors, ParseError};
+
     const TEST: &str = "Game 1: 3 blue, 4 red; 1 red, 2 green, 6 blue; 2 green
 Game 2: 1 blue, 2 green; 3 green, 4 blue, 1 red; 1 green, 1 blue
 Game 3: 8 green, 6 blue, 20 red; 5 blue, 4 red, 13 green; 5 green, 1 red
@@ -94,11 +231,105 @@ Game 5: 6 red, 1 blue, 3 green; 2 blue, 1 red, 2 green";
 
     #[test]
     fn test_1() {
-        assert_eq!(super::part1(TEST), 8);
+        assert_eq!(super::part1(TEST).unwrap(), 8);
     }
 
     #[test]
     fn test_2() {
-        assert_eq!(super::part2(TEST), 2286);
+        assert_eq!(super::part2(TEST).unwrap(), 2286);
+    }
+
+    #[test]
+    fn test_ids() {
+        let input = "Game 1234: 3 blue, 4 red
+Game 7: 20 red
+Game 1: 2 green";
+
+        assert_eq!(super::part1(input).unwrap(), 1235);
+        assert_eq!(super::part1(&TEST.replace("Game 3", "Game 30")).unwrap(), 8);
+        assert_eq!(
+            super::part1(&TEST.replace("Game 1:", "Game 10:")).unwrap(),
+            17
+        );
+    }
+
+    #[test]
+    fn test_line_breaks() {
+        assert_eq!(super::part1("Game 1: 3 blue\n\n").unwrap(), 1);
+        assert_eq!(super::part2(&format!("{TEST}\n\n\n")).unwrap(), 2286);
+        assert_eq!(super::part1(&TEST.replace('\n', "\r\n")).unwrap(), 8);
+        assert_eq!(
+            super::part1("Game 1: 3 blue\n\nGame 2: 4 red"),
+            Err(ParseError::MissingPrefix { line: 2 })
+        );
+    }
+
+    #[test]
+    fn test_errors() {
+        assert_eq!(
+            super::part1(&TEST.replace("2 green\nGame 2", "2 purple\nGame 2")),
+            Err(ParseError::UnknownColor {
+                line: 1,
+                color: "purple".to_owned()
+            })
+        );
+        assert_eq!(
+            super::part2(&TEST.replace("Game 4", "Gaem 4")),
+            Err(ParseError::MissingPrefix { line: 4 })
+        );
+        assert_eq!(
+            super::part1(&TEST.replace("Game 2", "Game two")),
+            Err(ParseError::InvalidId { line: 2 })
+        );
+        assert_eq!(
+            super::part1(&TEST.replace("20 red", "red")),
+            Err(ParseError::InvalidCubes { line: 3 })
+        );
+    }
+
+    #[test]
+    fn test_many_bags() {
+        let colors = Colors::STANDARD;
+        let bags = [
+            colors.bag(&[("red", 12), ("green", 13), ("blue", 14)]),
+            colors.bag(&[("red", 20), ("green", 13), ("blue", 15)]),
+            colors.bag(&[("red", 4), ("green", 3), ("blue", 6)]),
+            colors.bag(&[]),
+        ]
+        .map(Option::unwrap);
+
+        assert_eq!(
+            super::possible_games(TEST, &colors, &bags).unwrap(),
+            [8, 15, 3, 0]
+        );
+        assert!(colors.bag(&[("purple", 1)]).is_none());
+    }
+
+    #[test]
+    fn test_custom_colors() {
+        const INPUT: &str = "Game 1: 2 cyan, 1 magenta; 3 yellow, 1 cyan
+Game 2: 4 magenta; 1 cyan, 2 yellow, 1 black
+Game 3: 1 cyan; 1 cyan, 1 cyan";
+
+        let colors = Colors::new(&["cyan", "magenta", "yellow", "black"]);
+        let bag = colors
+            .bag(&[("cyan", 2), ("magenta", 4), ("yellow", 3)])
+            .unwrap();
+
+        assert_eq!(super::possible_games(INPUT, &colors, &[bag]).unwrap(), [4]);
+        assert_eq!(super::total_power(INPUT, &colors).unwrap(), 4 * 2);
+
+        let colors = Colors::new(&["cyan", "magenta", "yellow"]);
+        assert_eq!(
+            super::total_power(&INPUT[..INPUT.find('\n').unwrap()], &colors).unwrap(),
+            6
+        );
+        assert_eq!(
+            super::total_power(INPUT, &colors),
+            Err(ParseError::UnknownColor {
+                line: 2,
+                color: "black".to_owned()
+            })
+        );
     }
 }
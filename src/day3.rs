@@ -1,33 +1,81 @@
 #![allow(dead_code)]
 
+use std::io::{self, BufRead};
 use std::marker::PhantomData;
+use std::ops::RangeInclusive;
 
-#[cfg(test)]
-const WIDTH: usize = 10;
-#[cfg(not(test))]
-const WIDTH: usize = 140;
+use arrayvec::ArrayVec;
+use bstr::ByteSlice;
+
+pub mod graph;
 
+#[derive(Debug)]
 struct PreviousLine;
+#[derive(Debug)]
 struct CurrentLine;
 
 #[derive(Debug)]
 struct LineState<T> {
-    symbols: [bool; WIDTH],
-    numbers: [usize; WIDTH],
-    number_indices: [Option<usize>; WIDTH],
+    symbols: Vec<bool>,
+    numbers: Vec<usize>,
+    number_indices: Vec<Option<usize>>,
+    gears: Vec<Option<Gear>>,
     _marker: PhantomData<T>,
 }
 
+#[derive(Debug, Clone, Copy)]
+struct Gear {
+    count: u8,
+    ratio: usize,
+}
+
+impl Gear {
+    /// Counts a number next to the gear. Only gears next to exactly two numbers have a ratio, so
+    /// the numbers past the second are counted but not multiplied.
+    fn add(&mut self, value: usize) {
+        if self.count < 2 {
+            self.ratio *= value;
+        }
+
+        self.count = self.count.saturating_add(1);
+    }
+}
+
+impl<T> LineState<T> {
+    pub fn width(&self) -> usize {
+        self.symbols.len()
+    }
+
+    /// The columns adjacent to the given span, clamped to the line.
+    fn neighbours(&self, start: usize, end: usize) -> RangeInclusive<usize> {
+        start.saturating_sub(1)..=usize::min(end + 1, self.width() - 1)
+    }
+
+    pub fn add_to_gears(&mut self, start: usize, end: usize, value: usize) {
+        for i in self.neighbours(start, end) {
+            if let Some(gear) = &mut self.gears[i] {
+                gear.add(value);
+            }
+        }
+    }
+}
+
 impl LineState<CurrentLine> {
-    pub fn new() -> Self {
+    pub fn new(width: usize) -> Self {
         Self {
-            symbols: [false; WIDTH],
-            numbers: [0; WIDTH],
-            number_indices: [None; WIDTH],
+            symbols: vec![false; width],
+            numbers: vec![0; width],
+            number_indices: vec![None; width],
+            gears: vec![None; width],
             _marker: PhantomData,
         }
     }
 
+    pub fn register_gear(&mut self, index: usize, gear: Gear) {
+        self.symbols[index] = true;
+        self.gears[index] = Some(gear);
+    }
+
     pub fn register_symbol(&mut self, index: usize) {
         self.symbols[index] = true;
     }
@@ -45,6 +93,7 @@ impl LineState<CurrentLine> {
             symbols: self.symbols,
             numbers: self.numbers,
             number_indices: self.number_indices,
+            gears: self.gears,
             _marker: PhantomData,
         }
     }
@@ -56,12 +105,14 @@ impl LineState<PreviousLine> {
     }
 
     pub fn get_number(&mut self, index: usize) -> Option<usize> {
-        let range = index.saturating_sub(1)..=usize::min(index + 1, WIDTH - 1);
+        let range = self.neighbours(index, index);
 
         let mut sum = 0;
 
         for i in range {
-            let Some(idx) = self.number_indices[i].take() else { continue };
+            let Some(idx) = self.number_indices[i].take() else {
+                continue;
+            };
             sum += self.numbers[idx];
             self.numbers[idx] = 0;
         }
@@ -73,8 +124,47 @@ impl LineState<PreviousLine> {
         }
     }
 
-    pub fn reset(self) -> LineState<CurrentLine> {
-        LineState::<CurrentLine>::new()
+    /// The distinct numbers adjacent to the given column, without consuming them.
+    pub fn adjacent_numbers(&self, index: usize) -> ArrayVec<usize, 3> {
+        let mut starts = ArrayVec::<usize, 3>::new();
+
+        for i in self.neighbours(index, index) {
+            if let Some(start) = self.number_indices[i] {
+                if !starts.contains(&start) {
+                    starts.push(start);
+                }
+            }
+        }
+
+        starts
+            .into_iter()
+            .map(|start| self.numbers[start])
+            .collect()
+    }
+
+    /// The sum of the ratios of every gear adjacent to exactly two numbers.
+    pub fn gear_ratios(&self) -> usize {
+        self.gears
+            .iter()
+            .flatten()
+            .filter(|gear| gear.count == 2)
+            .map(|gear| gear.ratio)
+            .sum()
+    }
+
+    pub fn reset(mut self) -> LineState<CurrentLine> {
+        self.symbols.fill(false);
+        self.numbers.fill(0);
+        self.number_indices.fill(None);
+        self.gears.fill(None);
+
+        LineState::<CurrentLine> {
+            symbols: self.symbols,
+            numbers: self.numbers,
+            number_indices: self.number_indices,
+            gears: self.gears,
+            _marker: PhantomData,
+        }
     }
 }
 
@@ -90,17 +180,124 @@ enum State {
     Symbol,
 }
 
-#[aoc(day3, part1)]
-pub fn part1(input: &[u8]) -> usize {
-    let mut result = 0;
+/// The two lines of state kept while streaming a schematic, which together with the row being
+/// read make up a window of three rows.
+#[derive(Debug)]
+struct Window {
+    previous_line: LineState<PreviousLine>,
+    current_line: LineState<CurrentLine>,
+}
+
+impl Window {
+    fn new(width: usize) -> Self {
+        Self {
+            previous_line: LineState::<CurrentLine>::new(width).finish(),
+            current_line: LineState::<CurrentLine>::new(width),
+        }
+    }
+
+    fn width(&self) -> usize {
+        self.current_line.width()
+    }
+
+    /// Makes the current line the previous one, and reuses the old previous line for the next.
+    fn advance(&mut self) {
+        let empty = LineState::<CurrentLine>::new(0);
+        let previous_line = std::mem::replace(&mut self.previous_line, empty.finish());
+        let current_line = std::mem::replace(&mut self.current_line, previous_line.reset());
+
+        self.previous_line = current_line.finish();
+    }
+}
+
+/// The errors of a schematic, shared by the solvers and [`graph::Graph::new`].
+pub use crate::util::GridError as ParseError;
 
-    let mut current_line = LineState::<CurrentLine>::new();
-    let mut previous_line = LineState::<CurrentLine>::new().finish();
+/// Solves a schematic pushed one row at a time, without keeping more than a few rows of state.
+pub trait RowSolver {
+    /// Reads the next row, without its line break. Blank rows are skipped at the end of the
+    /// schematic, and rejected before any other row.
+    fn push_row(&mut self, row: &[u8]) -> Result<(), ParseError>;
+
+    /// The answer for every row pushed so far.
+    fn finish(self) -> usize;
+}
+
+/// The window and the line number shared by both solvers.
+#[derive(Debug, Default)]
+struct Rows {
+    window: Option<Window>,
+    lines: usize,
+    /// The line of the first blank row since the last row that was read.
+    blank_line: Option<usize>,
+}
+
+impl Rows {
+    /// Returns the window to read the row into, or `None` if the row is blank. Blank rows are only
+    /// skipped at the end of the schematic, so a row following one is an error, as in [`Grid`].
+    ///
+    /// [`Grid`]: crate::util::Grid
+    fn next_window(&mut self, row: &[u8]) -> Result<Option<&mut Window>, ParseError> {
+        self.lines += 1;
+
+        if row.is_empty() {
+            self.blank_line.get_or_insert(self.lines);
+            return Ok(None);
+        }
+
+        if let Some(line) = self.blank_line {
+            return Err(match &self.window {
+                Some(window) => ParseError::UnevenRow {
+                    line,
+                    expected: window.width(),
+                    found: 0,
+                },
+                // A blank first row makes the schematic zero columns wide.
+                None => ParseError::UnevenRow {
+                    line: self.lines,
+                    expected: 0,
+                    found: row.len(),
+                },
+            });
+        }
+
+        let window = self.window.get_or_insert_with(|| Window::new(row.len()));
+
+        if window.width() != row.len() {
+            return Err(ParseError::UnevenRow {
+                line: self.lines,
+                expected: window.width(),
+                found: row.len(),
+            });
+        }
+
+        Ok(Some(window))
+    }
+}
+
+/// Sums the numbers adjacent to a symbol.
+#[derive(Debug, Default)]
+pub struct PartNumbers {
+    rows: Rows,
+    result: usize,
+}
+
+impl RowSolver for PartNumbers {
+    fn push_row(&mut self, row: &[u8]) -> Result<(), ParseError> {
+        let Some(window) = self.rows.next_window(row)? else {
+            return Ok(());
+        };
+
+        let width = window.width();
+        let Window {
+            previous_line,
+            current_line,
+        } = window;
+        let result = &mut self.result;
 
-    for chunk in input.split(|b| *b == b'\n') {
         let mut state = State::Dot;
 
-        for (index, byte) in chunk.iter().chain(std::iter::once(&b'.')).enumerate() {
+        for (index, byte) in row.iter().chain(std::iter::once(&b'.')).enumerate() {
             let next_state = match *byte {
                 b'.' => State::Dot,
                 b'0'..=b'9' => State::Number {
@@ -153,8 +350,8 @@ pub fn part1(input: &[u8]) -> usize {
                     },
                     State::Dot,
                 ) => {
-                    if is_eligible || previous_line.has_symbol(usize::min(index, WIDTH - 1)) {
-                        result += value;
+                    if is_eligible || previous_line.has_symbol(usize::min(index, width - 1)) {
+                        *result += value;
                     } else {
                         current_line.register_number(start, end, value);
                     }
@@ -162,11 +359,11 @@ pub fn part1(input: &[u8]) -> usize {
                     State::Dot
                 }
                 (State::Number { value, .. }, State::Symbol) => {
-                    result += value;
+                    *result += value;
                     current_line.register_symbol(index);
 
                     if let Some(value) = previous_line.get_number(index) {
-                        result += value;
+                        *result += value;
                     }
 
                     State::Symbol
@@ -175,7 +372,7 @@ pub fn part1(input: &[u8]) -> usize {
                     current_line.register_symbol(index);
 
                     if let Some(value) = previous_line.get_number(index) {
-                        result += value;
+                        *result += value;
                     }
 
                     State::Symbol
@@ -184,11 +381,49 @@ pub fn part1(input: &[u8]) -> usize {
             };
         }
 
-        previous_line = current_line.finish();
-        current_line = LineState::<CurrentLine>::new();
+        window.advance();
+
+        Ok(())
+    }
+
+    fn finish(self) -> usize {
+        self.result
+    }
+}
+
+/// Feeds every row of a reader to the solver, holding a single row in memory at a time.
+///
+/// A row that does not match the width of the first one is reported as
+/// [`io::ErrorKind::InvalidData`], wrapping a [`ParseError`].
+pub fn solve_reader<S: RowSolver>(mut solver: S, mut reader: impl BufRead) -> io::Result<usize> {
+    let mut row = Vec::new();
+
+    while reader.read_until(b'\n', &mut row)? > 0 {
+        let line = row.strip_suffix(b"\n").unwrap_or(&row);
+        let line = line.strip_suffix(b"\r").unwrap_or(line);
+
+        solver
+            .push_row(line)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+
+        row.clear();
+    }
+
+    Ok(solver.finish())
+}
+
+/// Feeds every row of the input to the solver, splitting rows the same way as [`solve_reader`].
+fn solve_bytes<S: RowSolver>(mut solver: S, input: &[u8]) -> Result<usize, ParseError> {
+    for row in ByteSlice::lines(input) {
+        solver.push_row(row)?;
     }
 
-    result
+    Ok(solver.finish())
+}
+
+#[aoc(day3, part1)]
+pub fn part1(input: &[u8]) -> Result<usize, ParseError> {
+    solve_bytes(PartNumbers::default(), input)
 }
 
 #[derive(Debug)]
@@ -198,114 +433,117 @@ enum StateGears {
         start: usize,
         end: usize,
         value: usize,
-        is_eligible: bool,
     },
     Gear {
         count: u8,
+        ratio: usize,
     },
 }
 
-#[aoc(day3, part2)]
-pub fn part2(input: &[u8]) -> usize {
-    let mut result = 0;
+/// Registers a finished number, and adds it to the gears next to it in the previous line and to
+/// the gear directly to its left.
+fn finish_number(
+    previous_line: &mut LineState<PreviousLine>,
+    current_line: &mut LineState<CurrentLine>,
+    start: usize,
+    end: usize,
+    value: usize,
+) {
+    current_line.register_number(start, end, value);
+    previous_line.add_to_gears(start, end, value);
+
+    if let Some(Some(gear)) = start.checked_sub(1).map(|i| &mut current_line.gears[i]) {
+        gear.add(value);
+    }
+}
+
+/// Sums the ratios of the gears adjacent to exactly two numbers.
+#[derive(Debug, Default)]
+pub struct GearRatios {
+    rows: Rows,
+    result: usize,
+}
 
-    let mut current_line = LineState::<CurrentLine>::new();
-    let mut previous_line = LineState::<CurrentLine>::new().finish();
+impl RowSolver for GearRatios {
+    fn push_row(&mut self, row: &[u8]) -> Result<(), ParseError> {
+        let Some(window) = self.rows.next_window(row)? else {
+            return Ok(());
+        };
+
+        let Window {
+            previous_line,
+            current_line,
+        } = window;
 
-    for chunk in input.split(|b| *b == b'\n') {
         let mut state = StateGears::Other;
 
-        for (index, byte) in chunk.iter().chain(std::iter::once(&b'.')).enumerate() {
-            let next_state = match *byte {
-                b'*' => StateGears::Gear { count: 0 },
-                b'0'..=b'9' => StateGears::Number {
+        for (index, byte) in row.iter().chain(std::iter::once(&b'.')).enumerate() {
+            if let StateGears::Gear { count, ratio } = state {
+                current_line.register_gear(index - 1, Gear { count, ratio });
+            }
+
+            state = match (state, *byte) {
+                (StateGears::Number { start, value, .. }, b'0'..=b'9') => StateGears::Number {
+                    start,
+                    end: index,
+                    value: value * 10 + (*byte - b'0') as usize,
+                },
+                (_, b'0'..=b'9') => StateGears::Number {
                     start: index,
                     end: index,
                     value: (*byte - b'0') as usize,
-                    is_eligible: false,
                 },
-                _ => StateGears::Other,
-            };
+                (state, b'*') => {
+                    let mut gear = Gear { count: 0, ratio: 1 };
 
-            state = match (state, next_state) {
-                (StateGears::Other, StateGears::Number { value, start, .. }) => {
-                    StateGears::Number {
-                        value,
-                        start,
-                        end: start,
-                        is_eligible: previous_line.has_symbol(index)
-                            || previous_line.has_symbol(index.saturating_sub(1)),
-                    }
-                }
-                (StateGears::Gear { count }, StateGears::Number { value, start, .. }) => {
-                    StateGears::Number {
-                        value,
-                        start,
-                        end: start,
-                        is_eligible: true,
+                    for value in previous_line.adjacent_numbers(index) {
+                        gear.add(value);
                     }
-                }
-                (
-                    StateGears::Number {
-                        value: value_a,
-                        start,
-                        is_eligible,
-                        ..
-                    },
-                    StateGears::Number {
-                        value: value_b,
-                        end,
-                        ..
-                    },
-                ) => StateGears::Number {
-                    value: value_a * 10 + value_b,
-                    start,
-                    end,
-                    is_eligible: is_eligible || previous_line.has_symbol(index),
-                },
-                (
-                    StateGears::Number {
-                        start,
-                        end,
-                        value,
-                        is_eligible: _,
-                    },
-                    StateGears::Other,
-                ) => {
-                    current_line.register_number(start, end, value);
-                    StateGears::Other
-                }
-                (StateGears::Number { value, .. }, StateGears::Gear { count }) => {
-                    if let Some(value) = previous_line.get_number(index) {
-                        result += value;
-                    }
-
-                    StateGears::Gear { count }
-                }
-                (StateGears::Other | StateGears::Gear { .. }, StateGears::Gear { count }) => {
-                    current_line.register_symbol(index);
 
-                    if let Some(value) = previous_line.get_number(index) {
-                        result += value;
+                    if let StateGears::Number { start, end, value } = state {
+                        finish_number(previous_line, current_line, start, end, value);
+                        gear.add(value);
                     }
 
-                    StateGears::Gear { count }
+                    StateGears::Gear {
+                        count: gear.count,
+                        ratio: gear.ratio,
+                    }
                 }
-                (StateGears::Other | StateGears::Gear { .. }, StateGears::Other) => {
+                (StateGears::Number { start, end, value }, _) => {
+                    finish_number(previous_line, current_line, start, end, value);
                     StateGears::Other
                 }
+                (StateGears::Other | StateGears::Gear { .. }, _) => StateGears::Other,
             };
         }
 
-        previous_line = current_line.finish();
-        current_line = LineState::<CurrentLine>::new();
+        self.result += previous_line.gear_ratios();
+        window.advance();
+
+        Ok(())
+    }
+
+    fn finish(self) -> usize {
+        let last = self
+            .rows
+            .window
+            .map_or(0, |w| w.previous_line.gear_ratios());
+        self.result + last
     }
+}
 
-    result
+#[aoc(day3, part2)]
+pub fn part2(input: &[u8]) -> Result<usize, ParseError> {
+    solve_bytes(GearRatios::default(), input)
 }
 
 #[cfg(test)]
 mod tests {
+    use std::io::{self, BufReader, Read};
+
+    use super::{GearRatios, ParseError, PartNumbers, RowSolver};
+
     const TEST: &str = "467..114..
 ...*......
 ..35..633.
@@ -319,11 +557,223 @@ mod tests {
 
     #[test]
     fn test_1() {
-        assert_eq!(super::part1(TEST.as_bytes()), 4361);
+        assert_eq!(super::part1(TEST.as_bytes()).unwrap(), 4361);
+    }
+
+    #[test]
+    fn test_width() {
+        const NARROW: &str = "12...
+..*..
+...34
+.....
+7#8..";
+
+        assert_eq!(super::part1(NARROW.as_bytes()).unwrap(), 61);
+        assert_eq!(
+            super::part1(format!("{TEST}\n..........").as_bytes()).unwrap(),
+            4361
+        );
     }
 
     #[test]
     fn test_2() {
-        assert_eq!(super::part2(TEST.as_bytes()), 467835);
+        assert_eq!(super::part2(TEST.as_bytes()).unwrap(), 467835);
+    }
+
+    #[test]
+    fn test_gear_neighbours() {
+        assert_eq!(super::part2(b"12*..\n.....").unwrap(), 0);
+        assert_eq!(super::part2(b"12*34\n.....").unwrap(), 408);
+        assert_eq!(super::part2(b"12*34\n..5..").unwrap(), 0);
+        assert_eq!(super::part2(b"12...\n..*..\n...34").unwrap(), 408);
+        assert_eq!(super::part2(b"2.3\n.*.\n...").unwrap(), 6);
+        assert_eq!(super::part2(b"..7\n.*.\n5..").unwrap(), 35);
+        assert_eq!(super::part2(b"...\n.*.\n2.3").unwrap(), 6);
+        assert_eq!(super::part2(b"222\n.*.\n...").unwrap(), 0);
+        assert_eq!(super::part2(b"12.34\n..*..\n56.78").unwrap(), 0);
+        // Both gears share the 5.
+        assert_eq!(super::part2(b"2*5*3").unwrap(), 25);
+        // Gears next to more than two numbers are skipped without multiplying every number.
+        assert_eq!(
+            super::part2(b"1234567.1234567\n1234567*1234567\n1234567.1234567").unwrap(),
+            0
+        );
+    }
+
+    #[test]
+    fn test_gear_line_edges() {
+        // The numbers at the end of one line and the start of the next are not joined.
+        assert_eq!(super::part2(b"..5*\n2...").unwrap(), 0);
+        assert_eq!(super::part2(b"...2\n*5..").unwrap(), 0);
+        assert_eq!(super::part2(b"..5*\n...2").unwrap(), 10);
+        assert_eq!(super::part2(b"2...\n*5..").unwrap(), 10);
+    }
+
+    /// Produces the test schematic over and over, separated by rows of dots, without holding more
+    /// than one copy of it.
+    struct Repeated {
+        remaining: usize,
+        buffer: io::Cursor<Vec<u8>>,
+    }
+
+    impl Read for Repeated {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            let read = self.buffer.read(buf)?;
+
+            if read == 0 && self.remaining > 0 {
+                self.remaining -= 1;
+                self.buffer.set_position(0);
+                return self.buffer.read(buf);
+            }
+
+            Ok(read)
+        }
+    }
+
+    fn repeated(times: usize) -> BufReader<Repeated> {
+        let buffer = io::Cursor::new(format!("{TEST}\n..........\n").into_bytes());
+        let mut reader = Repeated {
+            remaining: times,
+            buffer,
+        };
+        reader
+            .buffer
+            .set_position(reader.buffer.get_ref().len() as u64);
+
+        // A small buffer splits rows across reads.
+        BufReader::with_capacity(7, reader)
+    }
+
+    #[test]
+    fn test_streaming() {
+        let times = 20_000;
+
+        assert_eq!(
+            super::solve_reader(PartNumbers::default(), repeated(times)).unwrap(),
+            4361 * times
+        );
+        assert_eq!(
+            super::solve_reader(GearRatios::default(), repeated(times)).unwrap(),
+            467835 * times
+        );
+
+        let crlf = TEST.replace('\n', "\r\n") + "\r\n\r\n";
+        assert_eq!(
+            super::solve_reader(GearRatios::default(), crlf.as_bytes()).unwrap(),
+            467835
+        );
+        assert_eq!(
+            super::solve_reader(PartNumbers::default(), io::empty()).unwrap(),
+            0
+        );
+    }
+
+    #[test]
+    fn test_push_rows() {
+        let mut solver = PartNumbers::default();
+
+        for row in TEST.lines() {
+            solver.push_row(row.as_bytes()).unwrap();
+        }
+        assert_eq!(solver.finish(), 4361);
+
+        let mut solver = GearRatios::default();
+        solver.push_row(b"467..").unwrap();
+        assert_eq!(
+            solver.push_row(b"...*..."),
+            Err(ParseError::UnevenRow {
+                line: 2,
+                expected: 5,
+                found: 7
+            })
+        );
+
+        let mut solver = PartNumbers::default();
+        solver.push_row(b"467..").unwrap();
+        solver.push_row(b"").unwrap();
+        solver.push_row(b"").unwrap();
+        assert_eq!(
+            solver.push_row(b"...*."),
+            Err(ParseError::UnevenRow {
+                line: 2,
+                expected: 5,
+                found: 0
+            })
+        );
+
+        let error = super::solve_reader(PartNumbers::default(), &b"12*\n.\n"[..]).unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn test_same_as_reader() {
+        let inputs: [&[u8]; 3] = [b"12\r\n..\r\n", b"12.\n...\n\n\n", b"12*\r\n...\r\n"];
+
+        for input in inputs {
+            assert_eq!(
+                super::part1(input).unwrap(),
+                super::solve_reader(PartNumbers::default(), input).unwrap()
+            );
+            assert_eq!(
+                super::part2(input).unwrap(),
+                super::solve_reader(GearRatios::default(), input).unwrap()
+            );
+        }
+
+        assert_eq!(super::part1(b"12\r\n..\r\n").unwrap(), 0);
+        assert_eq!(
+            super::part1(b"1....\n..\n.....\n"),
+            Err(ParseError::UnevenRow {
+                line: 2,
+                expected: 5,
+                found: 2
+            })
+        );
+    }
+
+    #[test]
+    fn test_blank_rows() {
+        let reader_error = |solver: PartNumbers, input: &[u8]| {
+            let error = super::solve_reader(solver, input).unwrap_err();
+            error
+                .into_inner()
+                .unwrap()
+                .downcast::<ParseError>()
+                .unwrap()
+        };
+
+        // The rows on either side of a blank row do not touch.
+        let blank = ParseError::UnevenRow {
+            line: 2,
+            expected: 2,
+            found: 0,
+        };
+        assert_eq!(super::part1(b"1.\n\n*."), Err(blank.clone()));
+        assert_eq!(super::part2(b"2.\n\n*3"), Err(blank.clone()));
+        assert_eq!(*reader_error(PartNumbers::default(), b"1.\n\n*."), blank);
+        assert_eq!(
+            super::solve_reader(GearRatios::default(), &b"2.\r\n\r\n\r\n*3"[..])
+                .unwrap_err()
+                .kind(),
+            io::ErrorKind::InvalidData
+        );
+        assert_eq!(super::graph::Graph::new(b"1.\n\n*."), Err(blank));
+
+        // A blank first row makes the schematic zero columns wide, as with the graph.
+        let leading = ParseError::UnevenRow {
+            line: 3,
+            expected: 0,
+            found: 2,
+        };
+        assert_eq!(super::part1(b"\n\n1*"), Err(leading.clone()));
+        assert_eq!(*reader_error(PartNumbers::default(), b"\n\n1*"), leading);
+        assert_eq!(super::graph::Graph::new(b"\n\n1*"), Err(leading));
+
+        // Trailing blank rows are still skipped.
+        assert_eq!(super::part1(b"1*\n\n\n").unwrap(), 1);
+        assert_eq!(
+            super::solve_reader(GearRatios::default(), &b"2*3\n\n"[..]).unwrap(),
+            6
+        );
     }
 }
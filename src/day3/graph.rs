@@ -0,0 +1,373 @@
+//! The graph between the symbols of a schematic and the numbers next to them, for debugging.
+
+use std::collections::HashMap;
+use std::fmt::Write;
+use std::ops::Range;
+
+use super::ParseError;
+use crate::util::Grid;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Symbol {
+    pub symbol: char,
+    pub x: usize,
+    pub y: usize,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Number {
+    pub value: usize,
+    /// The columns the digits span.
+    pub span: Range<usize>,
+    pub y: usize,
+}
+
+/// A bipartite graph with an edge between every symbol and every number next to it, including
+/// diagonally. Symbols and numbers are listed row by row, left to right.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Graph {
+    pub symbols: Vec<Symbol>,
+    pub numbers: Vec<Number>,
+    /// Indices into `symbols` and `numbers`, ordered by number.
+    pub edges: Vec<(usize, usize)>,
+}
+
+impl Graph {
+    /// Builds the graph of a schematic, with rows separated by `\n` or `\r\n`. Returns an error if
+    /// the rows are not all as wide as the first one.
+    pub fn new(input: &[u8]) -> Result<Self, ParseError> {
+        let grid = Grid::try_new(input)?;
+        let mut graph = Self::default();
+        let mut symbol_at = HashMap::new();
+
+        for (y, row) in grid.rows().enumerate() {
+            let mut x = 0;
+
+            while x < row.len() {
+                match row[x] {
+                    b'.' => x += 1,
+                    b'0'..=b'9' => {
+                        let start = x;
+                        let mut value = 0;
+
+                        while let Some(digit @ b'0'..=b'9') = row.get(x) {
+                            value = value * 10 + (digit - b'0') as usize;
+                            x += 1;
+                        }
+
+                        graph.numbers.push(Number {
+                            value,
+                            span: start..x,
+                            y,
+                        });
+                    }
+                    symbol => {
+                        symbol_at.insert((x, y), graph.symbols.len());
+                        graph.symbols.push(Symbol {
+                            symbol: symbol as char,
+                            x,
+                            y,
+                        });
+                        x += 1;
+                    }
+                }
+            }
+        }
+
+        for (index, number) in graph.numbers.iter().enumerate() {
+            let mut adjacent = number
+                .span
+                .clone()
+                .flat_map(|x| grid.neighbours8(x, number.y))
+                .filter_map(|position| symbol_at.get(&position).copied())
+                .collect::<Vec<_>>();
+
+            adjacent.sort_unstable();
+            adjacent.dedup();
+
+            graph
+                .edges
+                .extend(adjacent.into_iter().map(|symbol| (symbol, index)));
+        }
+
+        Ok(graph)
+    }
+
+    /// The indices of the numbers next to at least one symbol.
+    pub fn part_numbers(&self) -> impl Iterator<Item = usize> + '_ {
+        let mut numbers = self
+            .edges
+            .iter()
+            .map(|(_, number)| *number)
+            .collect::<Vec<_>>();
+        numbers.dedup();
+
+        numbers.into_iter()
+    }
+
+    /// The indices of the numbers that are not next to any symbol.
+    pub fn non_part_numbers(&self) -> impl Iterator<Item = usize> + '_ {
+        let mut parts = self.part_numbers().peekable();
+
+        (0..self.numbers.len()).filter(move |index| parts.next_if_eq(index).is_none())
+    }
+
+    /// The graph as JSON, with one symbol, number or edge per line so that two exports can be
+    /// diffed.
+    pub fn to_json(&self) -> String {
+        let mut json = String::from("{\n  \"symbols\": [");
+
+        for (index, symbol) in self.symbols.iter().enumerate() {
+            let separator = if index == 0 { "" } else { "," };
+            let _ = write!(
+                json,
+                "{separator}\n    {{\"id\": {index}, \"symbol\": \"{}\", \"x\": {}, \"y\": {}}}",
+                escape_json(symbol.symbol),
+                symbol.x,
+                symbol.y
+            );
+        }
+
+        json.push_str("\n  ],\n  \"numbers\": [");
+        let parts = self.part_numbers().collect::<Vec<_>>();
+
+        for (index, number) in self.numbers.iter().enumerate() {
+            let separator = if index == 0 { "" } else { "," };
+            let _ = write!(
+                json,
+                "{separator}\n    {{\"id\": {index}, \"value\": {}, \"start\": {}, \"end\": {}, \"y\": {}, \"part\": {}}}",
+                number.value,
+                number.span.start,
+                number.span.end,
+                number.y,
+                parts.binary_search(&index).is_ok()
+            );
+        }
+
+        json.push_str("\n  ],\n  \"edges\": [");
+
+        for (index, (symbol, number)) in self.edges.iter().enumerate() {
+            let separator = if index == 0 { "" } else { "," };
+            let _ = write!(
+                json,
+                "{separator}\n    {{\"symbol\": {symbol}, \"number\": {number}}}"
+            );
+        }
+
+        let non_parts = self
+            .non_part_numbers()
+            .map(|index| index.to_string())
+            .collect::<Vec<_>>();
+        let _ = write!(
+            json,
+            "\n  ],\n  \"non_part_numbers\": [{}]\n}}\n",
+            non_parts.join(", ")
+        );
+
+        json
+    }
+
+    /// The graph in the Graphviz DOT language. Numbers that are not part numbers are dashed.
+    pub fn to_dot(&self) -> String {
+        let mut dot = String::from("graph schematic {\n");
+
+        for (index, symbol) in self.symbols.iter().enumerate() {
+            let _ = writeln!(
+                dot,
+                "  s{index} [label=\"{} ({}, {})\", shape=box];",
+                escape_dot(symbol.symbol),
+                symbol.x,
+                symbol.y
+            );
+        }
+
+        let mut non_parts = self.non_part_numbers().peekable();
+
+        for (index, number) in self.numbers.iter().enumerate() {
+            let style = if non_parts.next_if_eq(&index).is_some() {
+                ", style=dashed"
+            } else {
+                ""
+            };
+
+            let _ = writeln!(
+                dot,
+                "  n{index} [label=\"{} ({}..{}, {})\"{style}];",
+                number.value, number.span.start, number.span.end, number.y
+            );
+        }
+
+        for (symbol, number) in &self.edges {
+            let _ = writeln!(dot, "  s{symbol} -- n{number};");
+        }
+
+        dot.push_str("}\n");
+        dot
+    }
+}
+
+/// Escapes a symbol for a double-quoted JSON string. Symbols outside printable ASCII are written
+/// as `\u00XX`, with the value of their byte.
+fn escape_json(symbol: char) -> String {
+    match symbol {
+        '"' | '\\' => format!("\\{symbol}"),
+        ' '..='~' => symbol.to_string(),
+        _ => format!("\\u{:04x}", symbol as u32),
+    }
+}
+
+/// Escapes a symbol for a double-quoted DOT label. A backslash starts a label escape of its own
+/// there, so symbols outside printable ASCII are written as the value of their byte in hex.
+fn escape_dot(symbol: char) -> String {
+    match symbol {
+        '"' | '\\' => format!("\\{symbol}"),
+        ' '..='~' => symbol.to_string(),
+        _ => format!("0x{:02x}", symbol as u32),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Graph;
+    use crate::day3::ParseError;
+
+    const TEST: &str = "467..114..
+...*......
+..35..633.
+......#...
+617*......
+.....+.58.
+..592.....
+......755.
+...$.*....
+.664.598..";
+
+    #[test]
+    fn test_graph() {
+        let graph = Graph::new(TEST.as_bytes()).unwrap();
+
+        assert_eq!(graph.symbols.len(), 6);
+        assert_eq!(graph.numbers.len(), 10);
+
+        let non_parts = graph
+            .non_part_numbers()
+            .map(|index| graph.numbers[index].value)
+            .collect::<Vec<_>>();
+        assert_eq!(non_parts, [114, 58]);
+
+        // The graph agrees with both parts.
+        let parts = graph.part_numbers().map(|index| graph.numbers[index].value);
+        assert_eq!(
+            parts.sum::<usize>(),
+            super::super::part1(TEST.as_bytes()).unwrap()
+        );
+
+        let ratios = graph
+            .symbols
+            .iter()
+            .enumerate()
+            .filter(|(_, symbol)| symbol.symbol == '*')
+            .map(|(index, _)| {
+                let numbers = graph
+                    .edges
+                    .iter()
+                    .filter(|(symbol, _)| *symbol == index)
+                    .map(|(_, number)| graph.numbers[*number].value)
+                    .collect::<Vec<_>>();
+
+                if numbers.len() == 2 {
+                    numbers[0] * numbers[1]
+                } else {
+                    0
+                }
+            });
+        assert_eq!(
+            ratios.sum::<usize>(),
+            super::super::part2(TEST.as_bytes()).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_shared_numbers() {
+        let graph = Graph::new(b"2*5*3\n.....").unwrap();
+
+        assert_eq!(graph.edges, [(0, 0), (0, 1), (1, 1), (1, 2)]);
+        assert_eq!(graph.numbers[1].span, 2..3);
+        assert_eq!(graph.non_part_numbers().count(), 0);
+    }
+
+    #[test]
+    fn test_export() {
+        let graph = Graph::new(b"12.\n..\"\n4..").unwrap();
+
+        assert_eq!(
+            graph.to_json(),
+            r#"{
+  "symbols": [
+    {"id": 0, "symbol": "\"", "x": 2, "y": 1}
+  ],
+  "numbers": [
+    {"id": 0, "value": 12, "start": 0, "end": 2, "y": 0, "part": true},
+    {"id": 1, "value": 4, "start": 0, "end": 1, "y": 2, "part": false}
+  ],
+  "edges": [
+    {"symbol": 0, "number": 0}
+  ],
+  "non_part_numbers": [1]
+}
+"#
+        );
+
+        assert_eq!(
+            graph.to_dot(),
+            r#"graph schematic {
+  s0 [label="\" (2, 1)", shape=box];
+  n0 [label="12 (0..2, 0)"];
+  n1 [label="4 (0..1, 2)", style=dashed];
+  s0 -- n0;
+}
+"#
+        );
+
+        assert_eq!(
+            Graph::new(b"").unwrap().to_json(),
+            "{\n  \"symbols\": [\n  ],\n  \"numbers\": [\n  ],\n  \"edges\": [\n  ],\n  \"non_part_numbers\": []\n}\n"
+        );
+    }
+
+    #[test]
+    fn test_line_breaks() {
+        let crlf = TEST.replace('\n', "\r\n") + "\r\n";
+
+        assert_eq!(
+            Graph::new(crlf.as_bytes()).unwrap(),
+            Graph::new(TEST.as_bytes()).unwrap()
+        );
+        assert_eq!(Graph::new(b"1*\r\n..\r\n").unwrap().symbols.len(), 1);
+        assert_eq!(
+            Graph::new(b"1....\n..\n.....\n"),
+            Err(ParseError::UnevenRow {
+                line: 2,
+                expected: 5,
+                found: 2
+            })
+        );
+    }
+
+    #[test]
+    fn test_escape() {
+        let graph = Graph::new(b"1\x01\\\xe9").unwrap();
+        let json = graph.to_json();
+
+        assert!(json.contains(r#""symbol": "\u0001""#));
+        assert!(json.contains(r#""symbol": "\\""#));
+        assert!(json.contains(r#""symbol": "\u00e9""#));
+        assert!(json.is_ascii());
+
+        let dot = graph.to_dot();
+
+        assert!(dot.contains(r#"s0 [label="0x01 (1, 0)""#));
+        assert!(dot.contains(r#"s1 [label="\\ (2, 0)""#));
+        assert!(dot.contains(r#"s2 [label="0xe9 (3, 0)""#));
+    }
+}
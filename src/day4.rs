@@ -1,105 +1,346 @@
-#[cfg(test)]
-const WINNING_COUNT: usize = 5;
-#[cfg(not(test))]
-const WINNING_COUNT: usize = 10;
+use std::collections::VecDeque;
+use std::error::Error;
+use std::fmt;
+use std::num::Saturating;
 
-#[cfg(test)]
-const NUMBER_COUNT: usize = 8;
-#[cfg(not(test))]
-const NUMBER_COUNT: usize = 25;
+use bstr::ByteSlice;
+use btoi::btou;
 
-#[cfg(test)]
-const PADDING: usize = 7;
-#[cfg(not(test))]
-const PADDING: usize = 9;
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ParseError {
+    MissingHeader {
+        line: usize,
+    },
+    MissingSeparator {
+        line: usize,
+    },
+    /// The numbers on the first line are not in columns of equal width.
+    UnevenColumns {
+        line: usize,
+    },
+    WrongLength {
+        line: usize,
+        expected: usize,
+        found: usize,
+    },
+    /// The column starting at the given byte offset is not a right-aligned number.
+    InvalidNumber {
+        line: usize,
+        offset: usize,
+    },
+    /// The number in the column starting at the given byte offset does not fit in a `u64`.
+    NumberTooLarge {
+        line: usize,
+        offset: usize,
+    },
+    /// The points of a card, or their total, do not fit in a `usize`.
+    TooManyPoints {
+        line: usize,
+    },
+    /// Counting the copies won up to this card overflowed.
+    TooManyCards {
+        line: usize,
+    },
+}
 
-#[aoc(day4, part1)]
-pub fn part1(input: &[u8]) -> usize {
-    const HIGHEST_BIT: usize =
-        0b_1000_0000_0000_0000_0000_0000_0000_0000_0000_0000_0000_0000_0000_0000_0000_0000;
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::MissingHeader { line } => write!(f, "line {line}: expected a `Card N:` header"),
+            Self::MissingSeparator { line } => {
+                write!(
+                    f,
+                    "line {line}: expected a ` | ` between the winning and drawn numbers"
+                )
+            }
+            Self::UnevenColumns { line } => {
+                write!(f, "line {line}: numbers are not in columns of equal width")
+            }
+            Self::WrongLength {
+                line,
+                expected,
+                found,
+            } => write!(
+                f,
+                "line {line}: expected {expected} bytes like the first line, found {found}"
+            ),
+            Self::InvalidNumber { line, offset } => {
+                write!(
+                    f,
+                    "line {line}, byte {offset}: expected a right-aligned number"
+                )
+            }
+            Self::NumberTooLarge { line, offset } => {
+                write!(
+                    f,
+                    "line {line}, byte {offset}: number does not fit in 64 bits"
+                )
+            }
+            Self::TooManyPoints { line } => write!(f, "line {line}: too many points to count"),
+            Self::TooManyCards { line } => {
+                write!(f, "line {line}: too many copies of cards to count")
+            }
+        }
+    }
+}
+
+impl Error for ParseError {}
+
+/// The positions of the columns of a scratchcard, which are the same on every line.
+#[derive(Debug, Clone, Copy)]
+struct Layout {
+    /// Width of the `Card N:` header, including the colon.
+    header: usize,
+    /// Offset of the `|` between the winning and drawn numbers.
+    separator: usize,
+    column_width: usize,
+    line_length: usize,
+}
+
+impl Layout {
+    /// Derives the layout from the first line of the input.
+    fn detect(input: &[u8]) -> Result<Self, ParseError> {
+        let line = lines(input).next().unwrap_or_default();
+        let header = line
+            .find_byte(b':')
+            .ok_or(ParseError::MissingHeader { line: 1 })?
+            + 1;
+        let separator = line
+            .find_byte(b'|')
+            .filter(|separator| *separator > header)
+            .ok_or(ParseError::MissingSeparator { line: 1 })?;
 
-    let mut result = 0;
+        let winning = &line[header..separator - 1];
+        let winning_count = winning.fields().count();
 
-    for mut line in input.split(|b| *b == b'\n') {
-        line = &line[PADDING..];
+        if winning_count == 0 || !winning.len().is_multiple_of(winning_count) {
+            return Err(ParseError::UnevenColumns { line: 1 });
+        }
+
+        let layout = Self {
+            header,
+            separator,
+            column_width: winning.len() / winning_count,
+            line_length: line.len(),
+        };
+
+        if !(line.len() - separator - 1).is_multiple_of(layout.column_width) {
+            return Err(ParseError::UnevenColumns { line: 1 });
+        }
+
+        Ok(layout)
+    }
 
-        let mut winning_numbers = [false; 100];
-        let mut points: usize = HIGHEST_BIT;
+    /// Validates the line against the layout, returning its winning and drawn number columns.
+    fn parse<'a>(
+        &self,
+        line_number: usize,
+        line: &'a [u8],
+    ) -> Result<(&'a [u8], &'a [u8]), ParseError> {
+        if line.len() != self.line_length {
+            return Err(ParseError::WrongLength {
+                line: line_number,
+                expected: self.line_length,
+                found: line.len(),
+            });
+        }
 
-        for _ in 0..WINNING_COUNT {
-            let (chunk, rest) = line.split_at(3);
-            let num = get_num(chunk);
-            winning_numbers[num as usize] = true;
+        if !line.starts_with(b"Card ") || line[self.header - 1] != b':' {
+            return Err(ParseError::MissingHeader { line: line_number });
+        }
 
-            line = rest;
+        if &line[self.separator - 1..=self.separator] != b" |" {
+            return Err(ParseError::MissingSeparator { line: line_number });
         }
 
-        line = &line[2..];
+        let winning = &line[self.header..self.separator - 1];
+        let numbers = &line[self.separator + 1..];
 
-        for _ in 0..NUMBER_COUNT {
-            let (chunk, rest) = line.split_at(3);
-            let num = get_num(chunk);
+        for (start, columns) in [(self.header, winning), (self.separator + 1, numbers)] {
+            for (i, column) in columns.chunks_exact(self.column_width).enumerate() {
+                let offset = start + i * self.column_width;
+                let digits = column.trim_start_with(|c| c == ' ');
 
-            points = points.rotate_left(u32::from(winning_numbers[num as usize]));
+                if digits.is_empty()
+                    || digits.len() == column.len()
+                    || !digits.iter().all(u8::is_ascii_digit)
+                {
+                    return Err(ParseError::InvalidNumber {
+                        line: line_number,
+                        offset,
+                    });
+                }
 
-            line = rest;
+                if btou::<u64>(digits).is_err() {
+                    return Err(ParseError::NumberTooLarge {
+                        line: line_number,
+                        offset,
+                    });
+                }
+            }
         }
 
-        result += points & !HIGHEST_BIT;
+        Ok((winning, numbers))
     }
 
-    result
-}
+    fn columns<'a>(&self, columns: &'a [u8]) -> impl Iterator<Item = u64> + 'a {
+        columns.chunks_exact(self.column_width).map(get_num)
+    }
 
-#[aoc(day4, part2)]
-pub fn part2(input: &[u8]) -> usize {
-    let mut cards = 0;
-    let mut card_multipliers = [1usize; 200];
+    /// Counts how many of the drawn numbers are winning numbers.
+    fn count_matches(&self, winning: &[u8], numbers: &[u8]) -> usize {
+        let winning_numbers = WinningNumbers::new(self.columns(winning));
 
-    for (i, mut line) in input.split(|b| *b == b'\n').enumerate() {
-        line = &line[PADDING..];
-        let multiplier = card_multipliers[i];
-        cards += multiplier;
+        self.columns(numbers)
+            .filter(|num| winning_numbers.contains(*num))
+            .count()
+    }
+}
 
-        let mut winning_numbers = [false; 100];
-        let mut winning_count = 0;
+/// The winning numbers of a card, as a bitset when they are all small enough to fit in one.
+enum WinningNumbers {
+    Small(u128),
+    Large(Vec<u64>),
+}
 
-        for _ in 0..WINNING_COUNT {
-            let (chunk, rest) = line.split_at(3);
-            let num = get_num(chunk);
-            winning_numbers[num as usize] = true;
+impl WinningNumbers {
+    fn new(numbers: impl Iterator<Item = u64>) -> Self {
+        let mut set = Self::Small(0);
 
-            line = rest;
+        for num in numbers {
+            match &mut set {
+                Self::Small(bits) if num < 128 => *bits |= 1 << num,
+                Self::Small(bits) => {
+                    let mut large = (0..128)
+                        .filter(|i| *bits & (1 << i) != 0)
+                        .collect::<Vec<_>>();
+                    large.push(num);
+                    set = Self::Large(large);
+                }
+                Self::Large(large) => large.push(num),
+            }
         }
 
-        line = &line[2..];
+        if let Self::Large(large) = &mut set {
+            large.sort_unstable();
+        }
 
-        for _ in 0..NUMBER_COUNT {
-            let (chunk, rest) = line.split_at(3);
-            let num = get_num(chunk);
+        set
+    }
 
-            winning_count += usize::from(winning_numbers[num as usize]);
-            line = rest;
+    fn contains(&self, num: u64) -> bool {
+        match self {
+            Self::Small(bits) => num < 128 && bits & (1 << num) != 0,
+            Self::Large(large) => large.binary_search(&num).is_ok(),
         }
+    }
+}
 
-        for next in 1..=winning_count {
-            card_multipliers[i + next] += multiplier;
-        }
+/// The lines of the input without their `\n` or `\r\n`, ignoring trailing blank lines. Trailing
+/// spaces are kept, as they are part of the last column.
+fn lines(input: &[u8]) -> impl Iterator<Item = &[u8]> {
+    input.trim_end_with(|c| matches!(c, '\n' | '\r')).lines()
+}
+
+#[aoc(day4, part1)]
+pub fn part1(input: &[u8]) -> Result<usize, ParseError> {
+    let layout = Layout::detect(input)?;
+    let mut result: usize = 0;
+
+    for (i, line) in lines(input).enumerate() {
+        let (winning, numbers) = layout.parse(i + 1, line)?;
+        let overflow = ParseError::TooManyPoints { line: i + 1 };
+
+        let points = match layout.count_matches(winning, numbers) {
+            0 => 0,
+            matches => u32::try_from(matches - 1)
+                .ok()
+                .and_then(|shift| 1usize.checked_shl(shift))
+                .ok_or(overflow)?,
+        };
+
+        result = result.checked_add(points).ok_or(overflow)?;
     }
 
-    cards
+    Ok(result)
+}
+
+/// A number type that copies of scratchcards can be counted in.
+pub trait CardCount: Copy {
+    const ZERO: Self;
+    const ONE: Self;
+
+    /// Adds two counts, returning `None` if the sum does not fit.
+    fn add(self, other: Self) -> Option<Self>;
+}
+
+macro_rules! impl_card_count {
+    ($($t:ty),*) => {
+        $(
+            impl CardCount for $t {
+                const ZERO: Self = 0;
+                const ONE: Self = 1;
+
+                fn add(self, other: Self) -> Option<Self> {
+                    self.checked_add(other)
+                }
+            }
+
+            impl CardCount for Saturating<$t> {
+                const ZERO: Self = Saturating(0);
+                const ONE: Self = Saturating(1);
+
+                fn add(self, other: Self) -> Option<Self> {
+                    Some(self + other)
+                }
+            }
+        )*
+    };
 }
 
-fn get_num(slice: &[u8]) -> u8 {
-    if slice[1] == b' ' {
-        slice[2] - b'0'
-    } else {
-        (slice[1] - b'0') * 10 + (slice[2] - b'0')
+impl_card_count!(u32, u64, usize, u128);
+
+/// Counts the scratchcards won, including the originals. Checked integer types fail with
+/// [`ParseError::TooManyCards`] on overflow, while [`Saturating`] types stop at their maximum.
+pub fn count_cards<C: CardCount>(input: &[u8]) -> Result<C, ParseError> {
+    let layout = Layout::detect(input)?;
+    let mut cards = C::ZERO;
+    // Copies of the upcoming cards, which only extends as far as the furthest win so far.
+    let mut card_multipliers = VecDeque::<C>::new();
+
+    for (i, line) in lines(input).enumerate() {
+        let (winning, numbers) = layout.parse(i + 1, line)?;
+        let overflow = ParseError::TooManyCards { line: i + 1 };
+
+        let multiplier = card_multipliers.pop_front().unwrap_or(C::ONE);
+        cards = cards.add(multiplier).ok_or(overflow)?;
+
+        for next in 0..layout.count_matches(winning, numbers) {
+            match card_multipliers.get_mut(next) {
+                Some(copies) => *copies = copies.add(multiplier).ok_or(overflow)?,
+                None => card_multipliers.push_back(C::ONE.add(multiplier).ok_or(overflow)?),
+            }
+        }
     }
+
+    Ok(cards)
+}
+
+#[aoc(day4, part2)]
+pub fn part2(input: &[u8]) -> Result<usize, ParseError> {
+    count_cards(input)
+}
+
+fn get_num(column: &[u8]) -> u64 {
+    btou(column.trim_start_with(|c| c == ' ')).unwrap()
 }
 
 #[cfg(test)]
 mod tests {
+    use std::num::Saturating;
+
+    use super::ParseError;
+
     const TEST: &str = "Card 1: 41 48 83 86 17 | 83 86  6 31 17  9 48 53
 Card 2: 13 32 20 16 61 | 61 30 68 82 17 32 24 19
 Card 3:  1 21 53 59 44 | 69 82 63 72 16 21 14  1
@@ -109,11 +350,175 @@ Card 6: 31 18 13 56 72 | 74 77 10 23 35 67 36 11";
 
     #[test]
     fn test_1() {
-        assert_eq!(super::part1(TEST.as_bytes()), 13);
+        assert_eq!(super::part1(TEST.as_bytes()).unwrap(), 13);
     }
 
     #[test]
     fn test_2() {
-        assert_eq!(super::part2(TEST.as_bytes()), 30);
+        assert_eq!(super::part2(TEST.as_bytes()).unwrap(), 30);
+    }
+
+    #[test]
+    fn test_layout() {
+        const WIDE: &str = "Card 1000: 41 48 83 86 17 | 83 86  6 31 17  9 48 53
+Card 1001: 13 32 20 16 61 | 61 30 68 82 17 32 24 19
+Card 1002:  1 21 53 59 44 | 69 82 63 72 16 21 14  1
+Card 1003: 41 92 73 84 69 | 59 84 76 51 58  5 54 83
+Card 1004: 87 83 26 28 32 | 88 30 70 12 93 22 82 36
+Card 1005: 31 18 13 56 72 | 74 77 10 23 35 67 36 11";
+
+        const PADDED: &str = "Card 1:  41  48 |  83  86   6  48
+Card 2:  13  32 |  32  30   1  13";
+
+        assert_eq!(super::part1(WIDE.as_bytes()).unwrap(), 13);
+        assert_eq!(super::part2(WIDE.as_bytes()).unwrap(), 30);
+        assert_eq!(super::part1(PADDED.as_bytes()).unwrap(), 3);
+    }
+
+    #[test]
+    fn test_line_breaks() {
+        assert_eq!(super::part1(b"Card 1: 41 48 | 83 41\n").unwrap(), 1);
+        assert_eq!(
+            super::part1(format!("{TEST}\n\n\n").as_bytes()).unwrap(),
+            13
+        );
+
+        let crlf = TEST.replace('\n', "\r\n") + "\r\n";
+        assert_eq!(super::part1(crlf.as_bytes()).unwrap(), 13);
+        assert_eq!(super::part2(crlf.as_bytes()).unwrap(), 30);
+    }
+
+    #[test]
+    fn test_malformed() {
+        let lines = TEST.lines().collect::<Vec<_>>();
+
+        let shifted = format!("{}\n{} ", lines[0], lines[1]);
+        assert_eq!(
+            super::part1(shifted.as_bytes()),
+            Err(ParseError::WrongLength {
+                line: 2,
+                expected: 48,
+                found: 49
+            })
+        );
+
+        let separator = format!("{}\n{}", lines[0], lines[1].replace('|', "/"));
+        assert_eq!(
+            super::part2(separator.as_bytes()),
+            Err(ParseError::MissingSeparator { line: 2 })
+        );
+
+        let number = format!("{}\n{}", lines[0], lines[1].replace("61 |", "6x |"));
+        assert_eq!(
+            super::part1(number.as_bytes()),
+            Err(ParseError::InvalidNumber {
+                line: 2,
+                offset: 19
+            })
+        );
+
+        assert_eq!(
+            super::part1(b"Card 1: 41 48 | 83 86 6"),
+            Err(ParseError::UnevenColumns { line: 1 })
+        );
+        assert_eq!(
+            super::part1(b"41 48 | 83 86"),
+            Err(ParseError::MissingHeader { line: 1 })
+        );
+    }
+
+    /// Generates cards that each win the given number of following cards.
+    fn synthetic(cards: usize, matches: usize) -> String {
+        (1..=cards)
+            .map(|i| {
+                let winning = (1..=5).map(|n| format!("{n:>3}")).collect::<String>();
+                let drawn = (1..=5)
+                    .map(|n| if n <= matches { n } else { n + 50 })
+                    .map(|n| format!("{n:>3}"))
+                    .collect::<String>();
+
+                format!("Card {i:>5}:{winning} |{drawn}")
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    #[test]
+    fn test_many_cards() {
+        assert_eq!(
+            super::part2(synthetic(10_000, 0).as_bytes()).unwrap(),
+            10_000
+        );
+        // Card `n` ends up with `n` copies, and wins past the last card are ignored.
+        assert_eq!(
+            super::part2(synthetic(10_000, 1).as_bytes()).unwrap(),
+            50_005_000
+        );
+        assert_eq!(
+            super::part1(synthetic(10_000, 5).as_bytes()).unwrap(),
+            160_000
+        );
+    }
+
+    #[test]
+    fn test_overflow() {
+        // Copies grow like the Fibonacci sequence, overflowing 64 bits within 100 cards.
+        let input = synthetic(10_000, 2);
+
+        assert!(matches!(
+            super::count_cards::<usize>(input.as_bytes()),
+            Err(ParseError::TooManyCards { line: 80..=100 })
+        ));
+        assert!(matches!(
+            super::count_cards::<u128>(input.as_bytes()),
+            Err(ParseError::TooManyCards { .. })
+        ));
+        assert_eq!(
+            super::count_cards::<Saturating<usize>>(input.as_bytes()).unwrap(),
+            Saturating(usize::MAX)
+        );
+        assert_eq!(
+            super::count_cards::<u128>(synthetic(100, 2).as_bytes()).unwrap(),
+            2_427_893_228_399_975_082_350
+        );
+    }
+
+    #[test]
+    fn test_large_numbers() {
+        const TEST: &str = "Card 1:   41  148 9999 |  148 9999   41    6
+Card 2:  130    2  127 |  127    3  128  130
+Card 3:    1    2    3 |    4    5    6    7";
+
+        assert_eq!(super::part1(TEST.as_bytes()).unwrap(), 4 + 2);
+        assert_eq!(super::part2(TEST.as_bytes()).unwrap(), 1 + 2 + 4);
+
+        let huge = format!("Card 1: {0} | {0}", u64::MAX);
+        assert_eq!(super::part1(huge.as_bytes()).unwrap(), 1);
+
+        let overflow = format!("Card 1: {0}0 | {0}0", u64::MAX);
+        assert_eq!(
+            super::part1(overflow.as_bytes()),
+            Err(ParseError::NumberTooLarge { line: 1, offset: 7 })
+        );
+    }
+
+    #[test]
+    fn test_many_matches() {
+        let card = |matches: usize| {
+            let winning = (1..=70).map(|n| format!("{n:>4}")).collect::<String>();
+            let drawn = (1..=70)
+                .map(|n| if n <= matches { n } else { n + 100 })
+                .map(|n| format!("{n:>4}"))
+                .collect::<String>();
+
+            format!("Card 1:{winning} |{drawn}")
+        };
+
+        assert_eq!(super::part1(card(64).as_bytes()).unwrap(), 1 << 63);
+        assert_eq!(
+            super::part1(card(65).as_bytes()),
+            Err(ParseError::TooManyPoints { line: 1 })
+        );
+        assert_eq!(super::part2(card(65).as_bytes()).unwrap(), 1);
     }
 }
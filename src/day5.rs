@@ -1,124 +1,526 @@
+use std::collections::{HashMap, VecDeque};
+use std::error::Error;
+use std::fmt;
 use std::ops::Range;
 
 use rangemap::RangeSet;
 
-#[aoc(day5, part1)]
-pub fn part1(input: &str) -> i64 {
-    let mut seeds = Vec::<i64>::with_capacity(32);
+/// The whole domain of a [`PiecewiseMap`].
+const DOMAIN: Range<i64> = i64::MIN..i64::MAX;
 
-    for section in input.split("\n\n") {
-        let (label, values) = section.split_once(':').unwrap();
+/// A map from numbers to numbers that shifts every segment of its domain by an offset, and leaves
+/// numbers outside every segment unchanged.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PiecewiseMap {
+    /// Sorted, non-overlapping and non-empty source ranges, with the offset added to numbers in
+    /// them.
+    segments: Vec<(Range<i64>, i64)>,
+}
 
-        if label == "seeds" {
-            seeds = values
-                .trim()
-                .split_ascii_whitespace()
-                .map(|v| v.parse().unwrap())
-                .collect();
-            continue;
+impl PiecewiseMap {
+    /// Creates a map from non-overlapping segments in any order.
+    pub fn new(segments: impl IntoIterator<Item = (Range<i64>, i64)>) -> Self {
+        let mut segments = segments
+            .into_iter()
+            .filter(|(range, offset)| !range.is_empty() && *offset != 0)
+            .collect::<Vec<_>>();
+
+        segments.sort_unstable_by_key(|(range, _)| range.start);
+
+        Self::merged(segments)
+    }
+
+    /// Creates a map from sorted segments, joining adjacent ones with the same offset.
+    fn merged(segments: impl IntoIterator<Item = (Range<i64>, i64)>) -> Self {
+        let mut merged = Vec::<(Range<i64>, i64)>::new();
+
+        for (range, offset) in segments {
+            if offset == 0 || range.is_empty() {
+                continue;
+            }
+
+            match merged.last_mut() {
+                Some((last, last_offset)) if last.end == range.start && *last_offset == offset => {
+                    last.end = range.end;
+                }
+                _ => merged.push((range, offset)),
+            }
         }
 
-        let mut mapped_seeds = [false; 64];
+        Self { segments: merged }
+    }
+
+    pub fn segments(&self) -> &[(Range<i64>, i64)] {
+        &self.segments
+    }
 
-        for line in values.trim().lines() {
-            let mut line_values = line.split_ascii_whitespace().map(|v| v.parse().unwrap());
-            let [dest, source, length]: [i64; 3] =
-                std::array::from_fn(|_| line_values.next().unwrap());
+    pub fn apply(&self, value: i64) -> i64 {
+        let index = self
+            .segments
+            .partition_point(|(range, _)| range.start <= value);
 
-            let diff = dest - source;
-            let source_range = source..(source + length + 1);
+        match index.checked_sub(1).map(|i| &self.segments[i]) {
+            Some((range, offset)) if range.contains(&value) => value + offset,
+            _ => value,
+        }
+    }
 
-            for (i, seed) in seeds.iter_mut().enumerate() {
-                if !mapped_seeds[i] && source_range.contains(seed) {
-                    *seed += diff;
-                    mapped_seeds[i] = true;
+    /// Maps many numbers at once, in the order of the sorted input, walking the segments once
+    /// instead of searching them for every number.
+    pub fn apply_sorted(&self, mut values: Vec<i64>) -> Vec<i64> {
+        values.sort_unstable();
+
+        let mut segments = self.segments.iter().peekable();
+
+        for value in &mut values {
+            while segments.next_if(|(range, _)| range.end <= *value).is_some() {}
+
+            if let Some((range, offset)) = segments.peek() {
+                if range.contains(value) {
+                    *value += offset;
                 }
             }
         }
+
+        values
+    }
+
+    pub fn apply_ranges(&self, ranges: &RangeSet<i64>) -> RangeSet<i64> {
+        let mut mapped = RangeSet::new();
+
+        for range in ranges.iter() {
+            for (piece, offset) in self.pieces_overlapping(range) {
+                mapped.insert(piece.add(offset));
+            }
+        }
+
+        mapped
+    }
+
+    /// Returns every number that this map sends into the given ranges.
+    pub fn preimage_ranges(&self, ranges: &RangeSet<i64>) -> RangeSet<i64> {
+        let mut preimage = RangeSet::new();
+
+        for (piece, offset) in self.pieces_overlapping(&DOMAIN) {
+            let image = piece.add(offset);
+
+            for range in ranges.overlapping(&image) {
+                let overlap = range.start.max(image.start)..range.end.min(image.end);
+                preimage.insert(overlap.add(-offset));
+            }
+        }
+
+        preimage
     }
 
-    seeds.into_iter().min().unwrap()
+    /// Returns the map applying this map and then `other`.
+    pub fn compose(&self, other: &Self) -> Self {
+        let mut segments = Vec::new();
+
+        for (piece, offset) in self.pieces_overlapping(&DOMAIN) {
+            let image = piece.add(offset);
+
+            for (sub_piece, other_offset) in other.pieces_overlapping(&image) {
+                segments.push((sub_piece.add(-offset), offset + other_offset));
+            }
+        }
+
+        Self::merged(segments)
+    }
+
+    /// Returns the inverse map, or `None` if two numbers are mapped to the same one.
+    pub fn invert(&self) -> Option<Self> {
+        let mut images = self
+            .pieces_overlapping(&DOMAIN)
+            .map(|(piece, offset)| (piece.add(offset), -offset))
+            .collect::<Vec<_>>();
+
+        images.sort_unstable_by_key(|(range, _)| range.start);
+
+        if images
+            .windows(2)
+            .any(|pair| pair[0].0.end > pair[1].0.start)
+        {
+            return None;
+        }
+
+        Some(Self::merged(images))
+    }
+
+    /// Splits the range into the parts covered by each segment and the gaps between them, with
+    /// the offset that applies to each part.
+    fn pieces_overlapping<'a>(
+        &'a self,
+        range: &Range<i64>,
+    ) -> impl Iterator<Item = (Range<i64>, i64)> + 'a {
+        let first = self
+            .segments
+            .partition_point(|(segment, _)| segment.end <= range.start);
+
+        let mut start = range.start;
+        let end = range.end;
+        let mut segments = self.segments[first..].iter().peekable();
+
+        std::iter::from_fn(move || {
+            if start >= end {
+                return None;
+            }
+
+            let (piece, offset) = match segments.peek() {
+                Some((segment, offset)) if segment.start <= start => {
+                    segments.next();
+                    (start..segment.end.min(end), *offset)
+                }
+                Some((segment, _)) => (start..segment.start.min(end), 0),
+                None => (start..end, 0),
+            };
+
+            start = piece.end;
+            Some((piece, offset))
+        })
+    }
 }
 
-trait RangeExt {
-    fn add(&self, offset: i64) -> Self;
+/// A parsed range along with the line it came from.
+type Segment = (usize, Range<i64>, i64);
+
+/// Builds a map from the ranges of one section, rejecting ranges that overlap.
+fn validate_segments(mut segments: Vec<Segment>) -> Result<PiecewiseMap, ParseError> {
+    segments.sort_unstable_by_key(|(_, range, _)| range.start);
+
+    for pair in segments.windows(2) {
+        let [(first_line, first, _), (second_line, second, _)] = pair else {
+            unreachable!()
+        };
+
+        if first.end > second.start {
+            return Err(ParseError::OverlappingRanges {
+                line: usize::max(*first_line, *second_line),
+                other_line: usize::min(*first_line, *second_line),
+            });
+        }
+    }
+
+    Ok(PiecewiseMap::new(
+        segments
+            .into_iter()
+            .map(|(_, range, offset)| (range, offset)),
+    ))
 }
 
-impl RangeExt for Range<i64> {
-    fn add(&self, offset: i64) -> Self {
-        (self.start + offset)..(self.end + offset)
+/// Parses a number of the almanac. Negative numbers are rejected, which keeps every offset and
+/// every sum of two offsets within an `i64` when maps are composed.
+fn parse_number(line: usize, value: &str) -> Result<i64, ParseError> {
+    value
+        .parse::<i64>()
+        .ok()
+        .filter(|value| *value >= 0)
+        .ok_or(ParseError::InvalidNumber { line })
+}
+
+/// A map from one category to the next, such as `seed-to-soil`.
+#[derive(Debug, Clone)]
+pub struct CategoryMap {
+    pub source: String,
+    pub destination: String,
+    pub map: PiecewiseMap,
+}
+
+#[derive(Debug, Clone)]
+pub struct Almanac {
+    pub seeds: Vec<i64>,
+    /// The maps in the order they appear.
+    pub maps: Vec<CategoryMap>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ParseError {
+    MissingSeeds,
+    /// A number is negative or does not fit in an `i64`.
+    InvalidNumber {
+        line: usize,
+    },
+    /// The line is neither a `X-to-Y map:` header nor a `destination source length` range.
+    InvalidLine {
+        line: usize,
+    },
+    /// The map does not start from the category the previous map led to.
+    BrokenChain {
+        line: usize,
+        expected: String,
+        found: String,
+    },
+    EmptyRange {
+        line: usize,
+    },
+    OverlappingRanges {
+        line: usize,
+        other_line: usize,
+    },
+    /// The start or end of a range does not fit in an `i64`.
+    Overflow {
+        line: usize,
+    },
+    OddSeedCount,
+    MissingCategory {
+        category: String,
+    },
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::MissingSeeds => write!(f, "line 1: expected a list of seeds"),
+            Self::InvalidNumber { line } => write!(f, "line {line}: invalid number"),
+            Self::InvalidLine { line } => {
+                write!(f, "line {line}: expected a map header or three numbers")
+            }
+            Self::BrokenChain {
+                line,
+                expected,
+                found,
+            } => write!(
+                f,
+                "line {line}: expected a map from {expected:?}, found one from {found:?}"
+            ),
+            Self::EmptyRange { line } => write!(f, "line {line}: range is empty"),
+            Self::OverlappingRanges { line, other_line } => {
+                write!(
+                    f,
+                    "line {line}: range overlaps the one on line {other_line}"
+                )
+            }
+            Self::Overflow { line } => write!(f, "line {line}: range does not fit in 64 bits"),
+            Self::OddSeedCount => write!(f, "line 1: seed ranges must come in pairs"),
+            Self::MissingCategory { category } => {
+                write!(f, "no map leads to the {category:?} category")
+            }
+        }
     }
 }
 
-#[aoc(day5, part2)]
-pub fn part2(input: &str) -> i64 {
-    let mut seeds = RangeSet::<i64>::new();
+impl Error for ParseError {}
+
+impl Almanac {
+    /// Parses the almanac, checking that the maps form a chain starting from the seeds and that
+    /// no map has overlapping source ranges.
+    pub fn parse(input: &str) -> Result<Self, ParseError> {
+        let mut lines = input.lines().enumerate().map(|(i, line)| (i + 1, line));
+
+        let seeds = lines
+            .next()
+            .and_then(|(_, line)| line.strip_prefix("seeds:"))
+            .ok_or(ParseError::MissingSeeds)?
+            .split_ascii_whitespace()
+            .map(|v| parse_number(1, v))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let mut maps = Vec::new();
+        let mut current: Option<(CategoryMap, Vec<Segment>)> = None;
+        let mut category = "seed".to_owned();
+
+        for (line_number, line) in lines.chain([(0, "")]) {
+            if line.trim().is_empty() {
+                if let Some((mut map, segments)) = current.take() {
+                    map.map = validate_segments(segments)?;
+                    maps.push(map);
+                }
+                continue;
+            }
+
+            if let Some(label) = line.strip_suffix(" map:") {
+                let (source, destination) = label
+                    .split_once("-to-")
+                    .ok_or(ParseError::InvalidLine { line: line_number })?;
+
+                if current.is_some() {
+                    return Err(ParseError::InvalidLine { line: line_number });
+                }
 
-    for section in input.split("\n\n") {
-        let (label, values) = section.split_once(':').unwrap();
+                if source != category {
+                    return Err(ParseError::BrokenChain {
+                        line: line_number,
+                        expected: category,
+                        found: source.to_owned(),
+                    });
+                }
+
+                category = destination.to_owned();
+                current = Some((
+                    CategoryMap {
+                        source: source.to_owned(),
+                        destination: destination.to_owned(),
+                        map: PiecewiseMap::default(),
+                    },
+                    Vec::new(),
+                ));
+                continue;
+            }
 
-        if label == "seeds" {
-            let seed_vec = values
-                .trim()
+            let Some((_, segments)) = &mut current else {
+                return Err(ParseError::InvalidLine { line: line_number });
+            };
+
+            let values = line
                 .split_ascii_whitespace()
-                .map(|v| v.parse::<i64>().unwrap())
-                .collect::<Vec<_>>();
+                .map(|v| parse_number(line_number, v))
+                .collect::<Result<Vec<_>, _>>()?;
+
+            let [dest, source, length] = values[..] else {
+                return Err(ParseError::InvalidLine { line: line_number });
+            };
 
-            for pair in seed_vec.chunks_exact(2) {
-                let (start, length) = (pair[0], pair[1]);
-                seeds.insert(start..(start + length));
+            if length <= 0 {
+                return Err(ParseError::EmptyRange { line: line_number });
             }
-            continue;
+
+            let overflow = ParseError::Overflow { line: line_number };
+            let end = source.checked_add(length).ok_or(overflow.clone())?;
+            dest.checked_add(length).ok_or(overflow.clone())?;
+            let offset = dest.checked_sub(source).ok_or(overflow)?;
+
+            segments.push((line_number, source..end, offset));
         }
 
-        let mut next_seeds = RangeSet::<i64>::new();
+        Ok(Self { seeds, maps })
+    }
+
+    /// Every category, in the order they are first mentioned.
+    pub fn categories(&self) -> Vec<&str> {
+        let mut categories = Vec::new();
+
+        for map in &self.maps {
+            for category in [&map.source, &map.destination] {
+                if !categories.contains(&category.as_str()) {
+                    categories.push(category);
+                }
+            }
+        }
+
+        categories
+    }
+
+    /// Finds the chain of maps leading from one category to another.
+    fn path(&self, from: &str, to: &str) -> Option<Vec<&CategoryMap>> {
+        // The map used to first reach each category, searched breadth-first.
+        let mut reached_by = HashMap::<&str, Option<&CategoryMap>>::from([(from, None)]);
+        let mut queue = VecDeque::from([from]);
 
-        for line in values.trim().lines() {
-            let mut line_values = line.split_ascii_whitespace().map(|v| v.parse().unwrap());
-            let [dest, source, length]: [i64; 3] =
-                std::array::from_fn(|_| line_values.next().unwrap());
+        while let Some(category) = queue.pop_front() {
+            if category == to {
+                let mut path = Vec::new();
+                let mut current = to;
 
-            let diff = dest - source;
-            let source_range = source..(source + length);
-            let mut to_remove = Vec::new();
+                while let Some(Some(map)) = reached_by.get(current) {
+                    path.push(*map);
+                    current = &map.source;
+                }
 
-            for overlapping in seeds.overlapping(&source_range) {
-                let range = match (
-                    source_range.contains(&overlapping.start),
-                    source_range.contains(&overlapping.end),
-                ) {
-                    (true, true) => {
-                        overlapping.clone()
-                    }
-                    (true, false) => {
-                        overlapping.start..source_range.end
-                    }
-                    (false, true) => {
-                        source_range.start..overlapping.end
-                    }
-                    (false, false) => {
-                        source_range.clone()
-                    }
-                };
-                
-                next_seeds.insert(range.add(diff));
-                to_remove.push(range);
+                path.reverse();
+                return Some(path);
             }
 
-            for range in to_remove {
-                seeds.remove(range);
+            for map in self.maps.iter().filter(|map| map.source == category) {
+                if !reached_by.contains_key(map.destination.as_str()) {
+                    reached_by.insert(&map.destination, Some(map));
+                    queue.push_back(&map.destination);
+                }
             }
         }
 
-        next_seeds.extend(seeds);
-        seeds = next_seeds;
+        None
+    }
+
+    /// Collapses the maps leading from one category to another into a single map, or returns
+    /// `None` if no chain of maps leads there.
+    pub fn map_between(&self, from: &str, to: &str) -> Option<PiecewiseMap> {
+        let path = self.path(from, to)?;
+
+        Some(
+            path.into_iter()
+                .fold(PiecewiseMap::default(), |map, next| map.compose(&next.map)),
+        )
+    }
+
+    /// Returns the numbers of the `to` category that correspond to the given numbers of the
+    /// `from` category. Works in both directions, so `location` to `seed` finds every seed that
+    /// lands in the given locations.
+    pub fn query(&self, from: &str, to: &str, ranges: &RangeSet<i64>) -> Option<RangeSet<i64>> {
+        if let Some(map) = self.map_between(from, to) {
+            Some(map.apply_ranges(ranges))
+        } else {
+            let map = self.map_between(to, from)?;
+            Some(map.preimage_ranges(ranges))
+        }
+    }
+
+    pub fn seed_to_location(&self) -> Result<PiecewiseMap, ParseError> {
+        self.map_between("seed", "location")
+            .ok_or(ParseError::MissingCategory {
+                category: "location".to_owned(),
+            })
+    }
+}
+
+trait RangeExt {
+    fn add(&self, offset: i64) -> Self;
+}
+
+impl RangeExt for Range<i64> {
+    fn add(&self, offset: i64) -> Self {
+        (self.start + offset)..(self.end + offset)
+    }
+}
+
+#[aoc(day5, part1)]
+pub fn part1(input: &str) -> Result<i64, ParseError> {
+    let almanac = Almanac::parse(input)?;
+    let map = almanac.seed_to_location()?;
+
+    map.apply_sorted(almanac.seeds)
+        .into_iter()
+        .min()
+        .ok_or(ParseError::MissingSeeds)
+}
+
+#[aoc(day5, part2)]
+pub fn part2(input: &str) -> Result<i64, ParseError> {
+    let almanac = Almanac::parse(input)?;
+    let mut seeds = RangeSet::<i64>::new();
+
+    if almanac.seeds.len() % 2 != 0 {
+        return Err(ParseError::OddSeedCount);
+    }
+
+    for pair in almanac.seeds.chunks_exact(2) {
+        let (start, length) = (pair[0], pair[1]);
+
+        if length <= 0 {
+            return Err(ParseError::EmptyRange { line: 1 });
+        }
+
+        let end = start
+            .checked_add(length)
+            .ok_or(ParseError::Overflow { line: 1 })?;
+
+        seeds.insert(start..end);
     }
 
-    seeds.into_iter().next().unwrap().start
+    let locations = almanac.seed_to_location()?.apply_ranges(&seeds);
+
+    locations
+        .into_iter()
+        .next()
+        .map(|range| range.start)
+        .ok_or(ParseError::MissingSeeds)
 }
 
 #[cfg(test)]
 mod tests {
+    use rangemap::RangeSet;
+
+    use super::{Almanac, ParseError, PiecewiseMap};
+
     const TEST: &str = "seeds: 79 14 55 13
 
 seed-to-soil map:
@@ -155,11 +557,257 @@ humidity-to-location map:
 
     #[test]
     fn test_1() {
-        assert_eq!(super::part1(TEST), 35);
+        assert_eq!(super::part1(TEST).unwrap(), 35);
     }
 
     #[test]
     fn test_2() {
-        assert_eq!(super::part2(TEST), 46);
+        assert_eq!(super::part2(TEST).unwrap(), 46);
+    }
+
+    #[test]
+    fn test_piecewise_map() {
+        let map = PiecewiseMap::new([(98..100, -48), (50..98, 2)]);
+
+        assert_eq!(map.apply(49), 49);
+        assert_eq!(map.apply(50), 52);
+        assert_eq!(map.apply(97), 99);
+        assert_eq!(map.apply(98), 50);
+        assert_eq!(map.apply(99), 51);
+        assert_eq!(map.apply(100), 100);
+
+        let ranges = RangeSet::from_iter([40..60, 99..101]);
+        assert_eq!(
+            map.apply_ranges(&ranges).into_iter().collect::<Vec<_>>(),
+            [40..50, 51..62, 100..101]
+        );
+
+        let inverse = map.invert().unwrap();
+        assert_eq!(inverse, PiecewiseMap::new([(50..52, 48), (52..100, -2)]));
+        assert_eq!(map.compose(&inverse), PiecewiseMap::default());
+        assert_eq!(PiecewiseMap::new([(0..10, 5)]).invert(), None);
+    }
+
+    #[test]
+    fn test_compose() {
+        let almanac = Almanac::parse(TEST).unwrap();
+        let map = almanac.seed_to_location().unwrap();
+
+        for seed in 0..120 {
+            let expected = almanac
+                .maps
+                .iter()
+                .fold(seed, |value, next| next.map.apply(value));
+
+            assert_eq!(map.apply(seed), expected);
+        }
+
+        let first = &almanac.maps[0].map;
+        let second = &almanac.maps[1].map;
+        assert_eq!(
+            first.compose(second).apply(79),
+            second.apply(first.apply(79))
+        );
+    }
+
+    #[test]
+    fn test_query() {
+        let almanac = Almanac::parse(TEST).unwrap();
+        let ranges = |ranges: &[(i64, i64)]| {
+            RangeSet::from_iter(ranges.iter().map(|(start, end)| *start..*end))
+        };
+
+        assert_eq!(
+            almanac.categories(),
+            [
+                "seed",
+                "soil",
+                "fertilizer",
+                "water",
+                "light",
+                "temperature",
+                "humidity",
+                "location"
+            ]
+        );
+
+        assert_eq!(
+            almanac.query("seed", "soil", &ranges(&[(79, 80)])),
+            Some(ranges(&[(81, 82)]))
+        );
+        assert_eq!(
+            almanac.query("soil", "fertilizer", &ranges(&[(81, 82)])),
+            Some(ranges(&[(81, 82)]))
+        );
+        assert_eq!(
+            almanac.query(
+                "seed",
+                "location",
+                &ranges(&[(79, 80), (14, 15), (55, 56), (13, 14)])
+            ),
+            Some(ranges(&[(35, 36), (43, 44), (82, 83), (86, 87)]))
+        );
+
+        // Backward queries find every number mapping into the ranges.
+        assert_eq!(
+            almanac.query("location", "seed", &ranges(&[(82, 83)])),
+            Some(ranges(&[(79, 80)]))
+        );
+        assert_eq!(
+            almanac.query("fertilizer", "soil", &ranges(&[(53, 54)])),
+            Some(ranges(&[(14, 15)]))
+        );
+
+        let seeds = almanac
+            .query("location", "seed", &ranges(&[(0, 50)]))
+            .unwrap();
+        let locations = almanac.seed_to_location().unwrap();
+        for seed in 0..200 {
+            assert_eq!(seeds.contains(&seed), locations.apply(seed) < 50);
+        }
+
+        assert_eq!(almanac.query("seed", "unknown", &ranges(&[(0, 1)])), None);
+    }
+
+    #[test]
+    fn test_validation() {
+        let replace = |from: &str, to: &str| super::part1(&TEST.replacen(from, to, 1));
+
+        assert_eq!(
+            replace("soil-to-fertilizer", "water-to-fertilizer"),
+            Err(ParseError::BrokenChain {
+                line: 7,
+                expected: "soil".to_owned(),
+                found: "water".to_owned()
+            })
+        );
+        assert_eq!(
+            replace("37 52 2", "37 52 0"),
+            Err(ParseError::EmptyRange { line: 9 })
+        );
+        assert_eq!(
+            replace("37 52 2", "37 36 2"),
+            Err(ParseError::OverlappingRanges {
+                line: 9,
+                other_line: 8
+            })
+        );
+        assert_eq!(
+            replace("50 98 2", "9223372036854775800 98 10"),
+            Err(ParseError::Overflow { line: 4 })
+        );
+        assert_eq!(
+            replace("50 98 2", "50 98"),
+            Err(ParseError::InvalidLine { line: 4 })
+        );
+        assert_eq!(
+            replace("52 50 48", "52 fifty 48"),
+            Err(ParseError::InvalidNumber { line: 5 })
+        );
+        assert_eq!(
+            super::part1(TEST.split("\n\nhumidity").next().unwrap()),
+            Err(ParseError::MissingCategory {
+                category: "location".to_owned()
+            })
+        );
+        assert_eq!(
+            super::part2(&TEST.replacen("55 13", "55", 1)),
+            Err(ParseError::OddSeedCount)
+        );
+        assert_eq!(super::part1(""), Err(ParseError::MissingSeeds));
+        assert_eq!(
+            super::part2(&TEST.replacen("55 13", "55 0", 1)),
+            Err(ParseError::EmptyRange { line: 1 })
+        );
+        assert_eq!(
+            replace("52 50 48", "52 -50 48"),
+            Err(ParseError::InvalidNumber { line: 5 })
+        );
+        assert_eq!(
+            replace("seeds: 79", "seeds: -79"),
+            Err(ParseError::InvalidNumber { line: 1 })
+        );
+
+        // Chaining two maps that each fit could overflow with negative numbers.
+        let chained = "seeds: 9223372036854775000
+
+seed-to-soil map:
+0 9223372036854775000 10
+
+soil-to-location map:
+-9223372036854775000 0 10";
+        assert_eq!(
+            super::part1(chained),
+            Err(ParseError::InvalidNumber { line: 7 })
+        );
+    }
+
+    #[test]
+    fn test_range_boundaries() {
+        let input = "seeds: 97 98 99 100 101 49 50
+
+seed-to-location map:
+50 98 2
+52 50 48";
+
+        let almanac = Almanac::parse(input).unwrap();
+        let map = almanac.seed_to_location().unwrap();
+        let expected = [97 + 2, 50, 51, 100, 101, 49, 52];
+
+        for (seed, location) in almanac.seeds.iter().zip(expected) {
+            assert_eq!(map.apply(*seed), location, "seed {seed}");
+        }
+
+        let mut sorted = expected.to_vec();
+        sorted.sort_unstable();
+
+        let mut mapped = map.apply_sorted(almanac.seeds.clone());
+        mapped.sort_unstable();
+        assert_eq!(mapped, sorted);
+
+        // Each step removes the seed with the lowest location, which walks through the boundaries:
+        // 49 is just before `50..98` and maps to itself, 98 and 99 are the first and last seeds of
+        // `98..100`, 50 and 97 are the first and last of `50..98`, and 100 = `98 + 2` is just past
+        // the end of `98..100`, which an off-by-one `length + 1` range would still map to 52.
+        let mut seeds = almanac.seeds.clone();
+        let maps = &input[input.find("\n\n").unwrap()..];
+
+        for (seed, lowest) in [(49, 49), (98, 50), (99, 51), (50, 52), (97, 99), (100, 100)] {
+            let seed_list = seeds.iter().map(i64::to_string).collect::<Vec<_>>();
+            let input = format!("seeds: {}{maps}", seed_list.join(" "));
+
+            assert_eq!(super::part1(&input).unwrap(), lowest, "seeds {seed_list:?}");
+            seeds.retain(|s| *s != seed);
+        }
+    }
+
+    #[test]
+    fn test_many_seeds() {
+        let seeds = (0..200_000)
+            .map(|i| i * 7919 % 1_000_003)
+            .collect::<Vec<i64>>();
+        let input = format!(
+            "seeds: {}\n\nseed-to-location map:\n10 500000 600000\n0 1100000 3",
+            seeds
+                .iter()
+                .map(i64::to_string)
+                .collect::<Vec<_>>()
+                .join(" ")
+        );
+
+        let almanac = Almanac::parse(&input).unwrap();
+        let map = almanac.seed_to_location().unwrap();
+
+        let mut expected = seeds
+            .iter()
+            .map(|&seed| map.apply(seed))
+            .collect::<Vec<_>>();
+        expected.sort_unstable();
+
+        let mut mapped = map.apply_sorted(seeds);
+        mapped.sort_unstable();
+
+        assert_eq!(mapped, expected);
+        assert_eq!(super::part1(&input).unwrap(), expected[0]);
     }
 }
@@ -1,67 +1,530 @@
+use std::error::Error;
+use std::fmt;
+use std::num::IntErrorKind;
+
 use crate::util;
 
-#[aoc(day6, part1)]
-pub fn part1(input: &str) -> i64 {
-    let (time_row, distance_row) = input.split_once('\n').unwrap();
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ParseError {
+    /// The input ends before the time or distance row.
+    MissingRow {
+        line: usize,
+    },
+    /// The row does not start with a label followed by `:`.
+    MissingLabel {
+        line: usize,
+    },
+    InvalidNumber {
+        line: usize,
+    },
+    /// A number on the line, or the joined number, does not fit in a `u128`.
+    NumberTooLarge {
+        line: usize,
+    },
+    /// The product of the number of ways to win every race does not fit in a `u128`.
+    ProductTooLarge,
+}
 
-    get_iter(time_row)
-        .zip(get_iter(distance_row))
-        .map(|(t, d)| get_roots(t, d + 0.001))
-        .map(|[from, to]| to - from + 1.0)
-        .product::<f64>() as i64
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::MissingRow { line } => write!(f, "line {line}: expected a row of numbers"),
+            Self::MissingLabel { line } => write!(f, "line {line}: expected a label and `:`"),
+            Self::InvalidNumber { line } => write!(f, "line {line}: invalid number"),
+            Self::NumberTooLarge { line } => {
+                write!(f, "line {line}: number does not fit in 128 bits")
+            }
+            Self::ProductTooLarge => {
+                write!(f, "product of the ways to win does not fit in 128 bits")
+            }
+        }
+    }
 }
 
-fn get_iter(line: &str) -> impl Iterator<Item = f64> + '_ {
-    let (_label, values) = line.split_once(':').unwrap();
-    values
-        .trim_start()
-        .split_ascii_whitespace()
-        .map(|val| val.parse::<f64>().unwrap())
+impl Error for ParseError {}
+
+#[aoc(day6, part1)]
+pub fn part1(input: &str) -> Result<u128, ParseError> {
+    product_of_wins(input, &Linear)
 }
 
 #[aoc(day6, part2)]
-pub fn part2(input: &str) -> i64 {
-    let (time_row, distance_row) = input.split_once('\n').unwrap();
-    let (time, distance) = (get_joined_number(time_row), get_joined_number(distance_row));
+pub fn part2(input: &str) -> Result<u128, ParseError> {
+    joined_wins(input, &Linear)
+}
+
+/// The product of the number of ways to win each race, reading one race per column.
+pub fn product_of_wins(input: &str, model: &impl BoatModel) -> Result<u128, ParseError> {
+    let (time_row, distance_row) = get_rows(input)?;
+    let times = get_numbers(1, time_row)?;
+    let distances = get_numbers(2, distance_row)?;
+
+    times
+        .into_iter()
+        .zip(distances)
+        .try_fold(1u128, |product, (t, d)| {
+            product
+                .checked_mul(model.ways_to_win(t, d))
+                .ok_or(ParseError::ProductTooLarge)
+        })
+}
+
+/// The number of ways to win the single race formed by joining the digits of every column.
+pub fn joined_wins(input: &str, model: &impl BoatModel) -> Result<u128, ParseError> {
+    let (time_row, distance_row) = get_rows(input)?;
+
+    let time = get_joined_number(1, time_row)?;
+    let distance = get_joined_number(2, distance_row)?;
+
+    Ok(model.ways_to_win(time, distance))
+}
+
+fn get_rows(input: &str) -> Result<(&str, &str), ParseError> {
+    let mut lines = input.lines();
+
+    let time_row = lines.next().ok_or(ParseError::MissingRow { line: 1 })?;
+    let distance_row = lines.next().ok_or(ParseError::MissingRow { line: 2 })?;
+
+    Ok((time_row, distance_row))
+}
+
+/// The numbers of the row, after its label.
+fn get_values(line_number: usize, line: &str) -> Result<impl Iterator<Item = &str>, ParseError> {
+    let (_label, values) = line
+        .split_once(':')
+        .ok_or(ParseError::MissingLabel { line: line_number })?;
+
+    Ok(values.split_ascii_whitespace())
+}
+
+fn get_numbers(line_number: usize, line: &str) -> Result<Vec<u128>, ParseError> {
+    get_values(line_number, line)?
+        .map(|val| {
+            val.parse::<u128>().map_err(|e| match e.kind() {
+                IntErrorKind::PosOverflow => ParseError::NumberTooLarge { line: line_number },
+                _ => ParseError::InvalidNumber { line: line_number },
+            })
+        })
+        .collect()
+}
+
+fn get_joined_number(line_number: usize, line: &str) -> Result<u128, ParseError> {
+    let values = get_values(line_number, line)?.collect::<Vec<_>>();
+
+    if values
+        .iter()
+        .any(|val| !val.bytes().all(|b| b.is_ascii_digit()))
+    {
+        return Err(ParseError::InvalidNumber { line: line_number });
+    }
+
+    let bytes = values.into_iter().flat_map(str::bytes);
+
+    util::checked_parse_number_from_iter(bytes)
+        .ok_or(ParseError::NumberTooLarge { line: line_number })
+}
+
+/// The number of hold times `h` in `0..=time` for which `h * (time - h) > distance`.
+///
+/// The winning hold times lie strictly between the roots of h^2 - Th + S = 0, which are
+/// (T +- sqrt(T^2 - 4S)) / 2. The integer square root only gives an estimate of the lower bound,
+/// which is then corrected by checking its neighbours.
+fn count_wins(time: u64, distance: u64) -> u64 {
+    let wins = |hold: u64| u128::from(hold) * u128::from(time - hold) > u128::from(distance);
+
+    let discriminant = u128::from(time).pow(2);
+    let Some(discriminant) = discriminant.checked_sub(4 * u128::from(distance)) else {
+        return 0;
+    };
+
+    let sqrt = discriminant.isqrt() as u64;
+    let mut from = (time - sqrt) / 2;
+
+    while from > 0 && wins(from - 1) {
+        from -= 1;
+    }
+
+    while from <= time / 2 && !wins(from) {
+        from += 1;
+    }
+
+    if from > time / 2 {
+        return 0;
+    }
+
+    // The winning hold times are symmetric around time / 2.
+    time - 2 * from + 1
+}
+
+/// How far a boat travels depending on how long its button is held.
+///
+/// The distance must strictly increase with the hold time up to some peak, and never increase
+/// after it, so that the winning hold times form a single range around the peak.
+pub trait BoatModel {
+    /// The distance travelled in a race of `time` after holding the button for `hold`, or `None`
+    /// if it does not fit in a `u128`.
+    fn distance(&self, hold: u128, time: u128) -> Option<u128>;
+
+    /// A hold time at which the distance is the largest.
+    fn peak(&self, time: u128) -> u128 {
+        binary_search(0, time, |hold| {
+            reach(self.distance(hold + 1, time)) <= reach(self.distance(hold, time))
+        })
+    }
+
+    /// The number of hold times in `0..=time` that travel further than `distance`.
+    fn ways_to_win(&self, time: u128, distance: u128) -> u128 {
+        count_wins_searching(self, time, distance)
+    }
+}
+
+/// Each millisecond of holding increases the speed by one.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Linear;
+
+impl BoatModel for Linear {
+    fn distance(&self, hold: u128, time: u128) -> Option<u128> {
+        hold.checked_mul(time - hold)
+    }
+
+    fn peak(&self, time: u128) -> u128 {
+        time / 2
+    }
+
+    fn ways_to_win(&self, time: u128, distance: u128) -> u128 {
+        match (u64::try_from(time), u64::try_from(distance)) {
+            (Ok(time), Ok(distance)) => count_wins(time, distance).into(),
+            _ => count_wins_searching(self, time, distance),
+        }
+    }
+}
+
+/// The speed is the square of the hold time.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct QuadraticAcceleration;
+
+impl BoatModel for QuadraticAcceleration {
+    fn distance(&self, hold: u128, time: u128) -> Option<u128> {
+        hold.checked_mul(hold)?.checked_mul(time - hold)
+    }
+}
+
+/// Like [`Linear`], but the speed stops increasing at `top_speed`.
+#[derive(Debug, Clone, Copy)]
+pub struct CappedSpeed {
+    pub top_speed: u128,
+}
+
+impl BoatModel for CappedSpeed {
+    fn distance(&self, hold: u128, time: u128) -> Option<u128> {
+        hold.min(self.top_speed).checked_mul(time - hold)
+    }
+
+    fn peak(&self, time: u128) -> u128 {
+        self.top_speed.min(time / 2)
+    }
+}
+
+/// Like [`Linear`], but once released the boat loses `decay` speed every millisecond until it
+/// stops.
+#[derive(Debug, Clone, Copy)]
+pub struct ChargeWithDecay {
+    pub decay: u128,
+}
+
+impl BoatModel for ChargeWithDecay {
+    fn distance(&self, hold: u128, time: u128) -> Option<u128> {
+        if self.decay == 0 {
+            return Linear.distance(hold, time);
+        }
+
+        let speed = hold;
+        let steps = (time - hold).min(speed.div_ceil(self.decay));
+
+        if steps == 0 {
+            return Some(0);
+        }
+
+        // The sum of the arithmetic series from `speed` down to `last`, which is still positive.
+        let last = speed - self.decay * (steps - 1);
 
-    let [from, to] = get_roots(time, distance + 0.001);
-    (to - from + 1.0) as i64
+        if steps.is_multiple_of(2) {
+            (steps / 2).checked_mul(speed.checked_add(last)?)
+        } else {
+            steps.checked_mul(last + self.decay * (steps - 1) / 2)
+        }
+    }
+}
+
+/// Orders distances, with the ones too large for a `u128` after every other one.
+fn reach(distance: Option<u128>) -> (bool, u128) {
+    (distance.is_none(), distance.unwrap_or_default())
 }
 
-fn get_joined_number(line: &str) -> f64 {
-    let (_label, values) = line.split_once(':').unwrap();
+/// The first value in `low..=high` for which the predicate holds, given that it holds for every
+/// value after it, or `high` if there is none.
+fn binary_search(mut low: u128, mut high: u128, predicate: impl Fn(u128) -> bool) -> u128 {
+    while low < high {
+        let mid = low + (high - low) / 2;
 
-    let bytes = values
-        .trim_start()
-        .split_ascii_whitespace()
-        .flat_map(str::bytes);
+        if predicate(mid) {
+            high = mid;
+        } else {
+            low = mid + 1;
+        }
+    }
 
-    util::parse_number_from_iter(bytes) as f64
+    low
 }
 
-/// h^2 - Th + S = 0
-/// (T +- sqrt(T^2 - 4S)) / 2
-fn get_roots(time: f64, distance: f64) -> [f64; 2] {
-    let sqrt = (time.powi(2) - distance * 4.0).sqrt();
+/// Counts the winning hold times by searching for the edges of the range around the peak.
+fn count_wins_searching<M: BoatModel + ?Sized>(model: &M, time: u128, distance: u128) -> u128 {
+    let peak = model.peak(time);
 
-    let from = ((time - sqrt) / 2.0).ceil();
-    let to = ((time + sqrt) / 2.0).floor();
+    let wins = |hold| model.distance(hold, time).is_none_or(|d| d > distance);
 
-    [from, to]
+    if !wins(peak) {
+        return 0;
+    }
+
+    let from = binary_search(0, peak, wins);
+    let to = binary_search(peak, time, |hold| hold == time || !wins(hold + 1));
+
+    to - from + 1
 }
 
 #[cfg(test)]
 mod tests {
+    use super::{
+        BoatModel, CappedSpeed, ChargeWithDecay, Linear, ParseError, QuadraticAcceleration,
+    };
+
     const TEST: &str = "Time:      7  15   30
 Distance:  9  40  200";
 
     #[test]
     fn test_1() {
-        assert_eq!(super::part1(TEST), 288);
+        assert_eq!(super::part1(TEST).unwrap(), 288);
     }
 
     #[test]
     fn test_2() {
-        assert_eq!(super::part2(TEST), 71503);
+        assert_eq!(super::part2(TEST).unwrap(), 71503);
+    }
+
+    #[test]
+    fn test_malformed() {
+        let large = "9".repeat(40);
+
+        assert_eq!(
+            super::part1(&format!("Time: {large}\nDistance: 1")),
+            Err(ParseError::NumberTooLarge { line: 1 })
+        );
+        assert_eq!(
+            super::part2(&format!("Time: 1\nDistance: {large}")),
+            Err(ParseError::NumberTooLarge { line: 2 })
+        );
+        assert_eq!(super::part1(""), Err(ParseError::MissingRow { line: 1 }));
+        assert_eq!(
+            super::part2("Time: 7 15 30"),
+            Err(ParseError::MissingRow { line: 2 })
+        );
+        assert_eq!(
+            super::part1("Time 7\nDistance: 9"),
+            Err(ParseError::MissingLabel { line: 1 })
+        );
+        assert_eq!(
+            super::part2("Time: 7\nDistance 9"),
+            Err(ParseError::MissingLabel { line: 2 })
+        );
+        assert_eq!(
+            super::part1("Time: 7 x\nDistance: 9 1"),
+            Err(ParseError::InvalidNumber { line: 1 })
+        );
+        assert_eq!(
+            super::part2("Time: 7\nDistance: -9"),
+            Err(ParseError::InvalidNumber { line: 2 })
+        );
+        assert_eq!(super::part1(&TEST.replace('\n', "\r\n")).unwrap(), 288);
+    }
+
+    #[test]
+    fn test_product_overflow() {
+        let max = u128::MAX;
+
+        assert_eq!(
+            super::part1(&format!("Time: {max} {max}\nDistance: 0 0")),
+            Err(ParseError::ProductTooLarge)
+        );
+        assert_eq!(
+            super::part1(&format!("Time: {max}\nDistance: 0")),
+            Ok(max - 1)
+        );
+    }
+
+    fn brute_force(time: u64, distance: u64) -> u64 {
+        (0..=time)
+            .filter(|hold| hold * (time - hold) > distance)
+            .count() as u64
+    }
+
+    #[test]
+    fn test_brute_force() {
+        for time in 0..60 {
+            for distance in 0..=(time * time / 4 + 2) {
+                assert_eq!(
+                    super::count_wins(time, distance),
+                    brute_force(time, distance),
+                    "time {time}, distance {distance}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_large_races() {
+        let max = u64::MAX;
+
+        assert_eq!(super::count_wins(max, 0), max - 1);
+        assert_eq!(super::count_wins(max, u64::MAX), max - 3);
+        assert_eq!(super::count_wins(max - 1, 0), max - 2);
+
+        // A distance that is exactly reached by holding for h is not a win, but one less is.
+        for hold in [1, 2, 1 << 20, (1 << 30) + 1, (1 << 31) - 1] {
+            let time = 1u64 << 33;
+            let distance = hold * (time - hold);
+
+            assert_eq!(super::count_wins(time, distance), time - 2 * hold - 1);
+            assert_eq!(super::count_wins(time, distance - 1), time - 2 * hold + 1);
+        }
+
+        // The best possible distance, and the ones just around it.
+        let time = u32::MAX as u64 * 2;
+        let best = (time / 2) * (time / 2);
+
+        assert_eq!(super::count_wins(time, best), 0);
+        assert_eq!(super::count_wins(time, best - 1), 1);
+        assert_eq!(super::count_wins(time, best - 4), 3);
+        assert_eq!(super::count_wins(time, best - 5), 5);
+    }
+
+    #[test]
+    fn test_wide() {
+        for time in 0..60 {
+            for distance in 0..=(time * time / 4 + 2) {
+                assert_eq!(
+                    super::count_wins_searching(&Linear, time as u128, distance as u128),
+                    brute_force(time, distance) as u128,
+                    "time {time}, distance {distance}"
+                );
+            }
+        }
+
+        let max = u64::MAX as u128;
+        assert_eq!(
+            Linear.ways_to_win(max, max),
+            super::count_wins(u64::MAX, u64::MAX) as u128
+        );
+
+        // The best hold time of 10^19 travels exactly 10^38.
+        let time = 2 * 10u128.pow(19);
+        let best = 10u128.pow(38);
+        assert_eq!(Linear.ways_to_win(time, best - 1), 1);
+        assert_eq!(Linear.ways_to_win(time, best - 4), 3);
+        assert_eq!(Linear.ways_to_win(u128::MAX, u128::MAX), u128::MAX - 3);
+    }
+
+    #[test]
+    fn test_joined_overflow() {
+        let input = "Time:      4000000000 0000000000
+Distance:  9 40 200";
+        assert_eq!(super::part2(input).unwrap(), 40_000_000_000_000_000_000 - 1);
+
+        let digits = "9".repeat(40);
+        assert_eq!(
+            super::part2(&format!("Time: {digits}\nDistance: 1")),
+            Err(super::ParseError::NumberTooLarge { line: 1 })
+        );
+        assert_eq!(
+            super::part2(&format!("Time: 1\nDistance: {digits}")),
+            Err(super::ParseError::NumberTooLarge { line: 2 })
+        );
+    }
+
+    /// Simulates a race millisecond by millisecond.
+    fn simulate(hold: u128, time: u128, speed: impl Fn(u128) -> u128, decay: u128) -> u128 {
+        let mut speed = speed(hold);
+        let mut distance = 0;
+
+        for _ in hold..time {
+            distance += speed;
+            speed = speed.saturating_sub(decay);
+        }
+
+        distance
+    }
+
+    fn check_model(model: &impl BoatModel, speed: impl Fn(u128) -> u128, decay: u128) {
+        for time in 0..40 {
+            let distances = (0..=time)
+                .map(|hold| simulate(hold, time, &speed, decay))
+                .collect::<Vec<_>>();
+
+            for (hold, expected) in distances.iter().enumerate() {
+                assert_eq!(model.distance(hold as u128, time), Some(*expected));
+            }
+
+            let best = *distances.iter().max().unwrap();
+            assert_eq!(distances[model.peak(time) as usize], best, "time {time}");
+
+            for distance in 0..=best + 1 {
+                let expected = distances.iter().filter(|d| **d > distance).count() as u128;
+                assert_eq!(
+                    model.ways_to_win(time, distance),
+                    expected,
+                    "time {time}, distance {distance}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_models() {
+        check_model(&Linear, |hold| hold, 0);
+        check_model(&QuadraticAcceleration, |hold| hold * hold, 0);
+
+        for top_speed in [0, 1, 5, 100] {
+            check_model(&CappedSpeed { top_speed }, |hold| hold.min(top_speed), 0);
+        }
+
+        for decay in [0, 1, 2, 3, 7] {
+            check_model(&ChargeWithDecay { decay }, |hold| hold, decay);
+        }
+    }
+
+    #[test]
+    fn test_models_input() {
+        assert_eq!(
+            super::product_of_wins(TEST, &QuadraticAcceleration).unwrap(),
+            5 * 13 * 27
+        );
+        assert_eq!(
+            super::joined_wins(
+                TEST,
+                &CappedSpeed {
+                    top_speed: u128::MAX
+                }
+            )
+            .unwrap(),
+            71503
+        );
+        assert_eq!(
+            super::joined_wins(
+                "Time: 340282366920938463463374607431768211455\nDistance: 0",
+                &ChargeWithDecay { decay: 1 }
+            )
+            .unwrap(),
+            u128::MAX - 1
+        );
     }
 }
@@ -1,192 +1,377 @@
-#![allow(long_running_const_eval)]
+use std::error::Error;
+use std::fmt;
 
+use arrayvec::ArrayVec;
 use bstr::ByteSlice;
 use btoi::btou;
 
-#[cfg(test)]
-const HAND_COUNT: usize = 5;
-#[cfg(not(test))]
-const HAND_COUNT: usize = 1000;
+#[cfg(feature = "hand-table")]
+pub mod table;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ParseError {
+    /// The line is not a hand and a bid separated by a space.
+    MalformedLine {
+        line: usize,
+    },
+    WrongHandSize {
+        line: usize,
+        expected: usize,
+        found: usize,
+    },
+    UnknownCard {
+        line: usize,
+        card: char,
+    },
+    InvalidBid {
+        line: usize,
+    },
+    /// The rules ask for more cards than [`MAX_HAND_SIZE`].
+    HandTooLarge {
+        found: usize,
+    },
+    /// The rules list more hand types than the strength of a hand can store.
+    TooManyHandTypes {
+        found: usize,
+    },
+    /// A card appears more than once in the card order.
+    DuplicateCard {
+        card: char,
+    },
+    /// A wildcard is missing from the card order.
+    UnknownWildcard {
+        card: char,
+    },
+}
 
-const MAX_HASH: usize = 0b11001100110011001100;
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::MalformedLine { line } => {
+                write!(
+                    f,
+                    "line {line}: expected a hand and a bid separated by a space"
+                )
+            }
+            Self::WrongHandSize {
+                line,
+                expected,
+                found,
+            } => write!(f, "line {line}: expected {expected} cards, found {found}"),
+            Self::UnknownCard { line, card } => write!(f, "line {line}: unknown card {card:?}"),
+            Self::InvalidBid { line } => write!(f, "line {line}: invalid bid"),
+            Self::HandTooLarge { found } => {
+                write!(f, "hands of {found} cards are larger than {MAX_HAND_SIZE}")
+            }
+            Self::TooManyHandTypes { found } => {
+                write!(
+                    f,
+                    "{found} hand types are more than {}",
+                    u8::MAX as usize + 1
+                )
+            }
+            Self::DuplicateCard { card } => {
+                write!(f, "card {card:?} appears more than once in the card order")
+            }
+            Self::UnknownWildcard { card } => {
+                write!(f, "wildcard {card:?} is not in the card order")
+            }
+        }
+    }
+}
 
-static HAND_SCORES: [u8; MAX_HASH + 1] = {
-    let mut scores = [0u8; MAX_HASH + 1];
+impl Error for ParseError {}
+
+/// Parses every `HAND BID` line, ignoring trailing blank lines. Line numbers in errors start at 1.
+fn parse_hands<'a>(
+    input: &'a [u8],
+    card_order: &'a [u8],
+    hand_size: usize,
+) -> impl Iterator<Item = Result<(&'a [u8], i64), ParseError>> + 'a {
+    input.trim_end().lines().enumerate().map(move |(i, line)| {
+        let line_number = i + 1;
+
+        let (hand, bid) = line
+            .split_once_str(" ")
+            .ok_or(ParseError::MalformedLine { line: line_number })?;
+
+        if hand.len() != hand_size {
+            return Err(ParseError::WrongHandSize {
+                line: line_number,
+                expected: hand_size,
+                found: hand.len(),
+            });
+        }
 
-    let mut a = 0u8;
+        if let Some(card) = hand.iter().find(|card| !card_order.contains(card)) {
+            return Err(ParseError::UnknownCard {
+                line: line_number,
+                card: *card as char,
+            });
+        }
 
-    while a < 13 {
-        let mut b = 0u8;
+        let bid = btou(bid).map_err(|_| ParseError::InvalidBid { line: line_number })?;
 
-        while b < 13 {
-            let mut c = 0u8;
+        Ok((hand, bid))
+    })
+}
 
-            while c < 13 {
-                let mut d = 0u8;
+/// Largest hand size supported by [`Rules`], as a hand and its type are packed into a `u128`.
+pub const MAX_HAND_SIZE: usize = 15;
 
-                while d < 13 {
-                    let mut e = 0u8;
+/// A hand type, described by the sizes of the card groups it requires, largest first.
+#[derive(Debug, Clone, Copy)]
+pub struct HandPattern<'a> {
+    pub name: &'a str,
+    pub groups: &'a [u8],
+}
 
-                    while e < 13 {
-                        let mut hash = 0u64;
-                        hash |= e as u64;
-                        hash |= (d as u64) << 4;
-                        hash |= (c as u64) << 8;
-                        hash |= (b as u64) << 12;
-                        hash |= (a as u64) << 16;
+impl HandPattern<'_> {
+    /// Whether a hand with the given non-wild groups (largest first) and number of wildcards can
+    /// form this pattern.
+    fn matches(&self, groups: &[u8], wildcards: u8) -> bool {
+        let missing: u32 = self
+            .groups
+            .iter()
+            .enumerate()
+            .map(|(i, needed)| needed.saturating_sub(groups.get(i).copied().unwrap_or(0)) as u32)
+            .sum();
+
+        missing <= wildcards as u32
+    }
+}
 
-                        scores[hash as usize] = get_hand_score([a, b, c, d, e]);
-                        e += 1;
-                    }
+pub const STANDARD_HANDS: &[HandPattern<'static>] = &[
+    HandPattern {
+        name: "High card",
+        groups: &[],
+    },
+    HandPattern {
+        name: "One pair",
+        groups: &[2],
+    },
+    HandPattern {
+        name: "Two pair",
+        groups: &[2, 2],
+    },
+    HandPattern {
+        name: "Three of a kind",
+        groups: &[3],
+    },
+    HandPattern {
+        name: "Full house",
+        groups: &[3, 2],
+    },
+    HandPattern {
+        name: "Four of a kind",
+        groups: &[4],
+    },
+    HandPattern {
+        name: "Five of a kind",
+        groups: &[5],
+    },
+];
+
+/// Describes a Camel Cards variant.
+#[derive(Debug, Clone, Copy)]
+pub struct Rules<'a> {
+    /// Cards from weakest to strongest, used to break ties between hands of the same type.
+    pub card_order: &'a [u8],
+    /// Cards that stand in for any other card when classifying a hand.
+    pub wildcards: &'a [u8],
+    /// Hand types from weakest to strongest. A hand is classified as the strongest type it can
+    /// form, and hands forming none of them rank as the weakest type.
+    pub hand_types: &'a [HandPattern<'a>],
+    pub hand_size: usize,
+}
 
-                    d += 1;
-                }
+impl Rules<'static> {
+    pub const STANDARD: Self = Self {
+        card_order: b"23456789TJQKA",
+        wildcards: b"",
+        hand_types: STANDARD_HANDS,
+        hand_size: 5,
+    };
 
-                c += 1;
-            }
+    pub const JOKERS: Self = Self {
+        card_order: b"J23456789TQKA",
+        wildcards: b"J",
+        hand_types: STANDARD_HANDS,
+        hand_size: 5,
+    };
+}
 
-            b += 1;
+impl Rules<'_> {
+    /// Checks that hands under these rules can be classified and ranked.
+    pub fn validate(&self) -> Result<(), ParseError> {
+        if self.hand_size > MAX_HAND_SIZE {
+            return Err(ParseError::HandTooLarge {
+                found: self.hand_size,
+            });
         }
 
-        a += 1;
-    }
+        if self.hand_types.len() > u8::MAX as usize + 1 {
+            return Err(ParseError::TooManyHandTypes {
+                found: self.hand_types.len(),
+            });
+        }
 
-    scores
-};
+        let mut seen = [false; 256];
 
-const fn get_hand_hash(hand: [u8; 5]) -> u64 {
-    let mut hash = 0u64;
-    let mut i = 0;
+        for card in self.card_order {
+            if std::mem::replace(&mut seen[*card as usize], true) {
+                return Err(ParseError::DuplicateCard {
+                    card: *card as char,
+                });
+            }
+        }
 
-    while i < 5 {
-        let card_value = get_card_value(hand[4 - i]);
-        hash |= (card_value as u64) << (i * 4);
+        if let Some(card) = self.wildcards.iter().find(|card| !seen[**card as usize]) {
+            return Err(ParseError::UnknownWildcard {
+                card: *card as char,
+            });
+        }
 
-        i += 1;
+        Ok(())
     }
-
-    hash
 }
 
-const fn get_card_value(card: u8) -> u8 {
-    match card {
-        b'2'..=b'9' => card - b'2',
-        b'T' => 8,
-        b'J' => 9,
-        b'Q' => 10,
-        b'K' => 11,
-        b'A' => 12,
-        _ => unreachable!(),
-    }
+/// Classifies hands by the sizes of their groups of equal non-wild cards, using a lookup of every
+/// possible combination of group sizes under the rules.
+struct Classifier {
+    ranks: [u8; 256],
+    is_wild: [bool; 256],
+    /// Weight of a card by the size of its group. These are powers of `hand_size + 1`, so summing
+    /// them over a hand gives a distinct key for every combination of group sizes.
+    weights: [u128; MAX_HAND_SIZE + 1],
+    /// Hand type of every key, sorted by key.
+    hand_types: Vec<(u128, u8)>,
 }
 
-const fn get_hand_score(hand: [u8; 5]) -> u8 {
-    let mut freqs = [0u8; 5];
-    let mut mapping = [usize::MAX; 13];
-    let mut i = 0;
-    let mut mapped = 0;
-
-    while i < 5 {
-        let card = hand[i];
-        if mapping[card as usize] == usize::MAX {
-            mapping[card as usize] = mapped;
-            mapped += 1;
+impl Classifier {
+    fn new(rules: &Rules) -> Result<Self, ParseError> {
+        rules.validate()?;
+
+        let mut ranks = [u8::MAX; 256];
+        let mut is_wild = [false; 256];
+        let mut weights = [0; MAX_HAND_SIZE + 1];
+
+        for (rank, card) in rules.card_order.iter().enumerate() {
+            ranks[*card as usize] = rank as u8;
         }
 
-        freqs[mapping[card as usize]] += 1;
-        i += 1;
-    }
+        for card in rules.wildcards {
+            is_wild[*card as usize] = true;
+        }
 
-    get_freq_score(freqs)
-}
+        for (size, weight) in weights.iter_mut().enumerate() {
+            *weight = (rules.hand_size as u128 + 1).pow(size as u32);
+        }
 
-const fn get_freq_score(freqs: [u8; 5]) -> u8 {
-    let sorted = sort_arr(freqs);
-
-    let hand_type = match sorted {
-        [.., 5] => HandType::FiveOfAKind,
-        [.., 2, 3] => HandType::FullHouse,
-        [.., 4] => HandType::FourOfAKind,
-        [.., 3] => HandType::ThreeOfAKind,
-        [.., 2, 2] => HandType::TwoPair,
-        [.., 2] => HandType::OnePair,
-        [.., 1] => HandType::HighCard,
-        _ => unreachable!(),
-    };
+        let mut classifier = Self {
+            ranks,
+            is_wild,
+            weights,
+            hand_types: Vec::new(),
+        };
 
-    hand_type as u8
-}
+        classifier.add_partitions(rules, &mut ArrayVec::new(), rules.hand_size);
+        classifier.hand_types.sort_unstable();
 
-const fn sort_arr(mut hand: [u8; 5]) -> [u8; 5] {
-    loop {
-        let mut swapped = false;
-        let mut i = 1;
-        while i < hand.len() {
-            if hand[i - 1] > hand[i] {
-                let left = hand[i - 1];
-                let right = hand[i];
-                hand[i - 1] = right;
-                hand[i] = left;
-                swapped = true;
-            }
-            i += 1;
-        }
-        if !swapped {
-            break;
+        Ok(classifier)
+    }
+
+    /// Registers the hand type of the given groups, and of every way to extend them with smaller
+    /// groups using the remaining cards.
+    fn add_partitions(
+        &mut self,
+        rules: &Rules,
+        groups: &mut ArrayVec<u8, MAX_HAND_SIZE>,
+        remaining: usize,
+    ) {
+        let wildcards = remaining as u8;
+        let key = groups
+            .iter()
+            .map(|size| *size as u128 * self.weights[*size as usize])
+            .sum();
+
+        let hand_type = rules
+            .hand_types
+            .iter()
+            .rposition(|pattern| pattern.matches(groups, wildcards))
+            .unwrap_or(0);
+
+        self.hand_types.push((key, hand_type as u8));
+
+        let largest = groups
+            .last()
+            .map_or(remaining, |size| usize::min(*size as usize, remaining));
+
+        for size in 1..=largest {
+            groups.push(size as u8);
+            self.add_partitions(rules, groups, remaining - size);
+            groups.pop();
         }
     }
-    hand
-}
 
-#[repr(u8)]
-#[derive(Debug, Copy, Clone)]
-enum HandType {
-    HighCard = 0,
-    OnePair = 1,
-    TwoPair = 2,
-    ThreeOfAKind = 3,
-    FullHouse = 4,
-    FourOfAKind = 5,
-    FiveOfAKind = 6,
-}
+    /// Returns the strength of a hand: the index of its type in the highest byte, followed by the
+    /// ranks of its cards for tie-breaking.
+    fn score_hand(&self, hand: &[u8]) -> u128 {
+        let mut key = 0;
+        let mut cards = 0u128;
 
-#[aoc(day7, part1)]
-pub fn part1(input: &[u8]) -> i64 {
-    let mut result = 0;
+        for card in hand {
+            cards = (cards << 8) | self.ranks[*card as usize] as u128;
 
-    let mut hands = [(0usize, 0i64); HAND_COUNT];
+            if !self.is_wild[*card as usize] {
+                let group_size = hand.iter().filter(|c| *c == card).count();
+                key += self.weights[group_size];
+            }
+        }
 
-    for (i, hand) in input
-        .split(|b| *b == b'\n')
-        .filter(|l| !l.is_empty())
-        .enumerate()
-    {
-        let (hand, bid) = hand.split_once_str(" ").unwrap();
-        let hand = hand.try_into().unwrap();
-        let hand = get_hand_hash(hand);
-        let bid = btou(bid).unwrap();
+        let index = self
+            .hand_types
+            .binary_search_by_key(&key, |(key, _)| *key)
+            .expect("every combination of group sizes is classified");
 
-        hands[i] = (hand as usize, bid);
+        (self.hand_types[index].1 as u128) << (MAX_HAND_SIZE * 8) | cards
     }
+}
 
-    hands.sort_unstable_by(|(a, _), (b, _)| HAND_SCORES[*a].cmp(&HAND_SCORES[*b]).then(a.cmp(b)));
+/// Ranks every hand under the given rules and sums each bid multiplied by its rank.
+pub fn total_winnings(input: &[u8], rules: &Rules) -> Result<i64, ParseError> {
+    let classifier = Classifier::new(rules)?;
 
-    for (i, (_, bid)) in hands.into_iter().enumerate() {
-        result += (i as i64 + 1) * bid;
-    }
+    let mut hands = parse_hands(input, rules.card_order, rules.hand_size)
+        .map(|hand| {
+            let (hand, bid) = hand?;
+            Ok((classifier.score_hand(hand), bid))
+        })
+        .collect::<Result<Vec<_>, ParseError>>()?;
+
+    hands.sort_unstable_by_key(|(strength, _)| *strength);
 
-    result
+    Ok(hands
+        .into_iter()
+        .enumerate()
+        .map(|(i, (_, bid))| (i as i64 + 1) * bid)
+        .sum())
 }
 
-// #[aoc(day7, part2)]
-// pub fn part2(input: &str) -> i64 {
-//     let mut result = 0;
-//
-//     result
-// }
+#[aoc(day7, part1)]
+pub fn part1(input: &[u8]) -> Result<i64, ParseError> {
+    total_winnings(input, &Rules::STANDARD)
+}
+
+#[aoc(day7, part2)]
+pub fn part2(input: &[u8]) -> Result<i64, ParseError> {
+    total_winnings(input, &Rules::JOKERS)
+}
 
 #[cfg(test)]
 mod tests {
+    use super::{Classifier, HandPattern, ParseError, Rules, MAX_HAND_SIZE};
+
     const TEST: &str = "32T3K 765
 T55J5 684
 KK677 28
@@ -195,11 +380,157 @@ QQQJA 483";
 
     #[test]
     fn test_1() {
-        assert_eq!(super::part1(TEST.as_bytes()), 6440);
+        assert_eq!(super::part1(TEST.as_bytes()).unwrap(), 6440);
     }
 
-    // #[test]
-    // fn test_2() {
-    //     assert_eq!(super::part2(TEST), 6440);
-    // }
+    #[test]
+    fn test_2() {
+        assert_eq!(super::part2(TEST.as_bytes()).unwrap(), 5905);
+    }
+
+    #[test]
+    fn test_variant() {
+        const HANDS: &[HandPattern] = &[
+            HandPattern {
+                name: "High card",
+                groups: &[],
+            },
+            HandPattern {
+                name: "One pair",
+                groups: &[2],
+            },
+            HandPattern {
+                name: "Three of a kind",
+                groups: &[3],
+            },
+            HandPattern {
+                name: "Two pair",
+                groups: &[2, 2],
+            },
+            HandPattern {
+                name: "Six of a kind",
+                groups: &[6],
+            },
+        ];
+
+        let rules = Rules {
+            card_order: b"*234",
+            wildcards: b"*",
+            hand_types: HANDS,
+            hand_size: 6,
+        };
+
+        // "222344" forms both three of a kind and two pair, and takes the stronger two pair.
+        let input = b"222344 100\n2*2**2 1000\n222234 1\n2*3344 10";
+
+        assert_eq!(super::total_winnings(input, &rules).unwrap(), 4321);
+        assert_eq!(
+            super::total_winnings(TEST.as_bytes(), &Rules::STANDARD).unwrap(),
+            6440
+        );
+    }
+
+    #[test]
+    fn test_hand_count() {
+        // Identical hands with a bid of 1 sum to 1 + 2 + ... + 1001.
+        let hands = "23456 1\n".repeat(1001);
+        let expected = 1001 * 1002 / 2;
+
+        assert_eq!(super::part1(hands.as_bytes()).unwrap(), expected);
+        assert_eq!(
+            super::part1(format!("{TEST}\n\n\n").as_bytes()).unwrap(),
+            6440
+        );
+    }
+
+    #[test]
+    fn test_malformed() {
+        assert_eq!(
+            super::part1(b"32T3K 765\nT55J5684"),
+            Err(ParseError::MalformedLine { line: 2 })
+        );
+        assert_eq!(
+            super::part1(b"32T3K 765\n\nKK677 28"),
+            Err(ParseError::MalformedLine { line: 2 })
+        );
+        assert_eq!(
+            super::part2(b"32T3 765"),
+            Err(ParseError::WrongHandSize {
+                line: 1,
+                expected: 5,
+                found: 4
+            })
+        );
+        assert_eq!(
+            super::part1(b"32T3X 765"),
+            Err(ParseError::UnknownCard { line: 1, card: 'X' })
+        );
+        assert_eq!(
+            super::part2(b"32T3K -765"),
+            Err(ParseError::InvalidBid { line: 1 })
+        );
+    }
+
+    #[test]
+    fn test_invalid_rules() {
+        let rules = |card_order, wildcards, hand_size| Rules {
+            card_order,
+            wildcards,
+            hand_types: super::STANDARD_HANDS,
+            hand_size,
+        };
+
+        assert_eq!(
+            super::total_winnings(TEST.as_bytes(), &rules(b"23456789TJQKA", b"", 16)),
+            Err(ParseError::HandTooLarge { found: 16 })
+        );
+        assert_eq!(
+            super::total_winnings(TEST.as_bytes(), &rules(b"23456789TJQKAT", b"", 5)),
+            Err(ParseError::DuplicateCard { card: 'T' })
+        );
+        assert_eq!(
+            super::total_winnings(TEST.as_bytes(), &rules(b"23456789TQKA", b"J", 5)),
+            Err(ParseError::UnknownWildcard { card: 'J' })
+        );
+
+        let hand_types = [super::STANDARD_HANDS[0]; 257];
+        assert_eq!(
+            Rules {
+                hand_types: &hand_types,
+                ..Rules::STANDARD
+            }
+            .validate(),
+            Err(ParseError::TooManyHandTypes { found: 257 })
+        );
+        assert_eq!(rules(b"*234", b"*", MAX_HAND_SIZE).validate(), Ok(()));
+    }
+
+    #[test]
+    fn test_classifier() {
+        let rules = Rules::JOKERS;
+        let classifier = Classifier::new(&rules).unwrap();
+
+        for i in 0..13usize.pow(5) {
+            let hand: [u8; 5] =
+                std::array::from_fn(|j| rules.card_order[i / 13usize.pow(j as u32) % 13]);
+
+            let mut groups = rules
+                .card_order
+                .iter()
+                .filter(|card| !rules.wildcards.contains(card))
+                .map(|card| hand.iter().filter(|c| *c == card).count() as u8)
+                .filter(|count| *count > 0)
+                .collect::<Vec<_>>();
+            groups.sort_unstable_by(|a, b| b.cmp(a));
+
+            let wildcards = 5 - groups.iter().sum::<u8>();
+            let expected = rules
+                .hand_types
+                .iter()
+                .rposition(|pattern| pattern.matches(&groups, wildcards))
+                .unwrap();
+
+            assert_eq!((classifier.score_hand(&hand) >> 120) as usize, expected);
+        }
+    }
 }
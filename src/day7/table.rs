@@ -0,0 +1,257 @@
+//! The original solver, classifying hands through a table of every possible hand that is built
+//! during const evaluation. Only compiled with the `hand-table` feature, for benchmarking.
+
+#![allow(long_running_const_eval)]
+
+use super::{parse_hands, ParseError};
+
+const TABLE_CARDS: &[u8] = b"23456789TJQKA";
+
+const MAX_HASH: usize = 0b11001100110011001100;
+
+static HAND_SCORES: [u8; MAX_HASH + 1] = {
+    let mut scores = [0u8; MAX_HASH + 1];
+
+    let mut a = 0u8;
+
+    while a < 13 {
+        let mut b = 0u8;
+
+        while b < 13 {
+            let mut c = 0u8;
+
+            while c < 13 {
+                let mut d = 0u8;
+
+                while d < 13 {
+                    let mut e = 0u8;
+
+                    while e < 13 {
+                        let mut hash = 0u64;
+                        hash |= e as u64;
+                        hash |= (d as u64) << 4;
+                        hash |= (c as u64) << 8;
+                        hash |= (b as u64) << 12;
+                        hash |= (a as u64) << 16;
+
+                        scores[hash as usize] = get_hand_score([a, b, c, d, e]);
+                        e += 1;
+                    }
+
+                    d += 1;
+                }
+
+                c += 1;
+            }
+
+            b += 1;
+        }
+
+        a += 1;
+    }
+
+    scores
+};
+
+const fn get_hand_hash(hand: [u8; 5]) -> u64 {
+    let mut hash = 0u64;
+    let mut i = 0;
+
+    while i < 5 {
+        let card_value = get_card_value(hand[4 - i]);
+        hash |= (card_value as u64) << (i * 4);
+
+        i += 1;
+    }
+
+    hash
+}
+
+const fn get_joker_hand_hash(hand: [u8; 5]) -> u64 {
+    let mut hash = 0u64;
+    let mut i = 0;
+
+    while i < 5 {
+        let card_value = get_joker_card_value(hand[4 - i]);
+        hash |= (card_value as u64) << (i * 4);
+
+        i += 1;
+    }
+
+    hash
+}
+
+const fn get_card_value(card: u8) -> u8 {
+    match card {
+        b'2'..=b'9' => card - b'2',
+        b'T' => 8,
+        b'J' => 9,
+        b'Q' => 10,
+        b'K' => 11,
+        b'A' => 12,
+        _ => unreachable!(),
+    }
+}
+
+const fn get_joker_card_value(card: u8) -> u8 {
+    match card {
+        b'J' => 0,
+        b'2'..=b'9' => card - b'1',
+        b'T' => 9,
+        b'Q' => 10,
+        b'K' => 11,
+        b'A' => 12,
+        _ => unreachable!(),
+    }
+}
+
+/// Replaces every joker with the most common other card, yielding the strongest hand type.
+fn substitute_jokers(mut hand: [u8; 5]) -> [u8; 5] {
+    let mut best = (0, b'A');
+
+    for card in hand {
+        if card == b'J' {
+            continue;
+        }
+
+        let count = hand.iter().filter(|c| **c == card).count();
+
+        if count > best.0 {
+            best = (count, card);
+        }
+    }
+
+    for card in &mut hand {
+        if *card == b'J' {
+            *card = best.1;
+        }
+    }
+
+    hand
+}
+
+const fn get_hand_score(hand: [u8; 5]) -> u8 {
+    let mut freqs = [0u8; 5];
+    let mut mapping = [usize::MAX; 13];
+    let mut i = 0;
+    let mut mapped = 0;
+
+    while i < 5 {
+        let card = hand[i];
+        if mapping[card as usize] == usize::MAX {
+            mapping[card as usize] = mapped;
+            mapped += 1;
+        }
+
+        freqs[mapping[card as usize]] += 1;
+        i += 1;
+    }
+
+    get_freq_score(freqs)
+}
+
+const fn get_freq_score(freqs: [u8; 5]) -> u8 {
+    let sorted = sort_arr(freqs);
+
+    let hand_type = match sorted {
+        [.., 5] => HandType::FiveOfAKind,
+        [.., 2, 3] => HandType::FullHouse,
+        [.., 4] => HandType::FourOfAKind,
+        [.., 3] => HandType::ThreeOfAKind,
+        [.., 2, 2] => HandType::TwoPair,
+        [.., 2] => HandType::OnePair,
+        [.., 1] => HandType::HighCard,
+        _ => unreachable!(),
+    };
+
+    hand_type as u8
+}
+
+const fn sort_arr(mut hand: [u8; 5]) -> [u8; 5] {
+    loop {
+        let mut swapped = false;
+        let mut i = 1;
+        while i < hand.len() {
+            if hand[i - 1] > hand[i] {
+                let left = hand[i - 1];
+                let right = hand[i];
+                hand[i - 1] = right;
+                hand[i] = left;
+                swapped = true;
+            }
+            i += 1;
+        }
+        if !swapped {
+            break;
+        }
+    }
+    hand
+}
+
+#[repr(u8)]
+#[derive(Debug, Copy, Clone)]
+enum HandType {
+    HighCard = 0,
+    OnePair = 1,
+    TwoPair = 2,
+    ThreeOfAKind = 3,
+    FullHouse = 4,
+    FourOfAKind = 5,
+    FiveOfAKind = 6,
+}
+
+pub fn part1(input: &[u8]) -> Result<i64, ParseError> {
+    let mut hands = parse_hands(input, TABLE_CARDS, 5)
+        .map(|hand| {
+            let (hand, bid) = hand?;
+            Ok((get_hand_hash(hand.try_into().unwrap()) as usize, bid))
+        })
+        .collect::<Result<Vec<_>, ParseError>>()?;
+
+    hands.sort_unstable_by(|(a, _), (b, _)| HAND_SCORES[*a].cmp(&HAND_SCORES[*b]).then(a.cmp(b)));
+
+    Ok(hands
+        .into_iter()
+        .enumerate()
+        .map(|(i, (_, bid))| (i as i64 + 1) * bid)
+        .sum())
+}
+
+pub fn part2(input: &[u8]) -> Result<i64, ParseError> {
+    let mut hands = parse_hands(input, TABLE_CARDS, 5)
+        .map(|hand| {
+            let (hand, bid) = hand?;
+            let hand = hand.try_into().unwrap();
+            let score = HAND_SCORES[get_hand_hash(substitute_jokers(hand)) as usize];
+
+            Ok((score, get_joker_hand_hash(hand), bid))
+        })
+        .collect::<Result<Vec<_>, ParseError>>()?;
+
+    hands.sort_unstable_by(|(a, a_hash, _), (b, b_hash, _)| a.cmp(b).then(a_hash.cmp(b_hash)));
+
+    Ok(hands
+        .into_iter()
+        .enumerate()
+        .map(|(i, (_, _, bid))| (i as i64 + 1) * bid)
+        .sum())
+}
+
+#[cfg(test)]
+mod tests {
+    const TEST: &str = "32T3K 765
+T55J5 684
+KK677 28
+KTJJT 220
+QQQJA 483";
+
+    #[test]
+    fn test_1() {
+        assert_eq!(super::part1(TEST.as_bytes()).unwrap(), 6440);
+    }
+
+    #[test]
+    fn test_2() {
+        assert_eq!(super::part2(TEST.as_bytes()).unwrap(), 5905);
+    }
+}
@@ -1,5 +1,8 @@
 #![allow(dead_code)]
 
+use std::error::Error;
+use std::fmt;
+
 pub fn parse_number(bytes: &[u8]) -> i64 {
     bytes
         .iter()
@@ -12,3 +15,278 @@ pub fn parse_number_from_iter(bytes: impl Iterator<Item = u8>) -> i64 {
         .map(|b| b - b'0')
         .fold(0i64, |sum, digit| sum * 10 + (digit as i64))
 }
+
+/// Like [`parse_number_from_iter`], but returns `None` if the number does not fit in a `u128`.
+pub fn checked_parse_number_from_iter(mut bytes: impl Iterator<Item = u8>) -> Option<u128> {
+    bytes.try_fold(0u128, |sum, b| {
+        sum.checked_mul(10)?.checked_add((b - b'0') as u128)
+    })
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum GridError {
+    /// The row is not as wide as the first one.
+    UnevenRow {
+        line: usize,
+        expected: usize,
+        found: usize,
+    },
+    /// The row does not end with the same line break as the first one.
+    MixedLineEndings { line: usize },
+}
+
+impl fmt::Display for GridError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::UnevenRow {
+                line,
+                expected,
+                found,
+            } => write!(f, "line {line}: expected {expected} columns, found {found}"),
+            Self::MixedLineEndings { line } => {
+                write!(f, "line {line}: line break differs from the first line")
+            }
+        }
+    }
+}
+
+impl Error for GridError {}
+
+/// A rectangular grid of bytes, borrowed from newline-separated input without copying.
+#[derive(Debug, Clone, Copy)]
+pub struct Grid<'a> {
+    bytes: &'a [u8],
+    width: usize,
+    height: usize,
+    /// Distance between the starts of two rows, including the line break.
+    stride: usize,
+}
+
+impl<'a> Grid<'a> {
+    /// Creates a grid as wide as the first line, with rows separated by `\n` or `\r\n`. Trailing
+    /// line breaks are ignored.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the rows are not all as wide as the first one. See [`Grid::try_new`].
+    pub fn new(bytes: &'a [u8]) -> Self {
+        Self::try_new(bytes).unwrap_or_else(|e| panic!("{e}"))
+    }
+
+    /// Like [`Grid::new`], but returns an error if a row is not as wide as the first one or ends
+    /// with a different line break.
+    pub fn try_new(bytes: &'a [u8]) -> Result<Self, GridError> {
+        let end = bytes
+            .iter()
+            .rposition(|b| !matches!(b, b'\n' | b'\r'))
+            .map_or(0, |i| i + 1);
+        let bytes = &bytes[..end];
+
+        let first = bytes.split(|b| *b == b'\n').next().unwrap_or_default();
+        let crlf = first.ends_with(b"\r");
+        let width = first.len() - crlf as usize;
+        let stride = width + 1 + crlf as usize;
+
+        let mut lines = bytes.split(|b| *b == b'\n').peekable();
+        let mut height = 0;
+
+        while let Some(line) = lines.next().filter(|_| !bytes.is_empty()) {
+            height += 1;
+
+            let row = line.strip_suffix(b"\r").unwrap_or(line);
+
+            // Trailing line breaks are trimmed, so the last row never ends with `\r`.
+            if (row.len() < line.len()) != (crlf && lines.peek().is_some()) {
+                return Err(GridError::MixedLineEndings { line: height });
+            }
+
+            if row.len() != width {
+                return Err(GridError::UnevenRow {
+                    line: height,
+                    expected: width,
+                    found: row.len(),
+                });
+            }
+        }
+
+        Ok(Self {
+            bytes,
+            width,
+            height,
+            stride,
+        })
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    pub fn get(&self, x: usize, y: usize) -> Option<u8> {
+        if x < self.width && y < self.height {
+            Some(self.bytes[y * self.stride + x])
+        } else {
+            None
+        }
+    }
+
+    /// Like [`Grid::get`], but accepts coordinates that may be negative.
+    pub fn get_signed(&self, x: isize, y: isize) -> Option<u8> {
+        self.get(usize::try_from(x).ok()?, usize::try_from(y).ok()?)
+    }
+
+    pub fn row(&self, y: usize) -> Option<&'a [u8]> {
+        if y < self.height {
+            let start = y * self.stride;
+            Some(&self.bytes[start..start + self.width])
+        } else {
+            None
+        }
+    }
+
+    pub fn rows(&self) -> impl Iterator<Item = &'a [u8]> + '_ {
+        (0..self.height).filter_map(|y| self.row(y))
+    }
+
+    pub fn column(&self, x: usize) -> impl Iterator<Item = u8> + '_ {
+        (0..self.height).filter_map(move |y| self.get(x, y))
+    }
+
+    pub fn columns(&self) -> impl Iterator<Item = impl Iterator<Item = u8> + '_> + '_ {
+        (0..self.width).map(|x| self.column(x))
+    }
+
+    /// The positions directly above, left, right and below, that are inside the grid.
+    pub fn neighbours4(&self, x: usize, y: usize) -> impl Iterator<Item = (usize, usize)> + '_ {
+        self.offsets(x, y, &[(0, -1), (-1, 0), (1, 0), (0, 1)])
+    }
+
+    /// The positions surrounding the given one, including diagonals, that are inside the grid.
+    pub fn neighbours8(&self, x: usize, y: usize) -> impl Iterator<Item = (usize, usize)> + '_ {
+        self.offsets(
+            x,
+            y,
+            &[
+                (-1, -1),
+                (0, -1),
+                (1, -1),
+                (-1, 0),
+                (1, 0),
+                (-1, 1),
+                (0, 1),
+                (1, 1),
+            ],
+        )
+    }
+
+    fn offsets(
+        &self,
+        x: usize,
+        y: usize,
+        offsets: &'static [(isize, isize)],
+    ) -> impl Iterator<Item = (usize, usize)> + '_ {
+        offsets.iter().filter_map(move |(dx, dy)| {
+            let x = x.checked_add_signed(*dx)?;
+            let y = y.checked_add_signed(*dy)?;
+
+            self.get(x, y).map(|_| (x, y))
+        })
+    }
+
+    /// The positions of every occurrence of the byte, row by row.
+    pub fn find_all(&self, byte: u8) -> impl Iterator<Item = (usize, usize)> + '_ {
+        self.bytes
+            .iter()
+            .enumerate()
+            .filter(move |(i, b)| **b == byte && i % self.stride < self.width)
+            .map(|(i, _)| (i % self.stride, i / self.stride))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Grid, GridError};
+
+    const TEST: &[u8] = b"ab.\n.c.\n..a\n";
+
+    #[test]
+    fn test_grid() {
+        let grid = Grid::new(TEST);
+
+        assert_eq!((grid.width(), grid.height()), (3, 3));
+        assert_eq!(grid.get(1, 1), Some(b'c'));
+        assert_eq!(grid.get(3, 0), None);
+        assert_eq!(grid.get(0, 3), None);
+        assert_eq!(grid.get_signed(-1, 0), None);
+        assert_eq!(grid.get_signed(2, 2), Some(b'a'));
+
+        assert_eq!(grid.rows().collect::<Vec<_>>(), [b"ab.", b".c.", b"..a"]);
+        assert_eq!(grid.row(3), None);
+        assert_eq!(grid.column(1).collect::<Vec<_>>(), b"bc.");
+        assert_eq!(grid.columns().count(), 3);
+        assert_eq!(grid.find_all(b'a').collect::<Vec<_>>(), [(0, 0), (2, 2)]);
+        assert_eq!(grid.find_all(b'\n').count(), 0);
+
+        let without_newline = Grid::new(&TEST[..TEST.len() - 1]);
+        assert_eq!((without_newline.width(), without_newline.height()), (3, 3));
+        assert_eq!(Grid::new(b"").height(), 0);
+    }
+
+    #[test]
+    fn test_line_breaks() {
+        let crlf = Grid::new(b"ab.\r\n.c.\r\n..a\r\n\r\n");
+
+        assert_eq!((crlf.width(), crlf.height()), (3, 3));
+        assert_eq!(crlf.rows().collect::<Vec<_>>(), [b"ab.", b".c.", b"..a"]);
+        assert_eq!(crlf.find_all(b'a').collect::<Vec<_>>(), [(0, 0), (2, 2)]);
+        assert_eq!(crlf.find_all(b'\r').count(), 0);
+
+        let trailing = Grid::new(b"ab.\n.c.\n..a\n\n\n");
+        assert_eq!((trailing.width(), trailing.height()), (3, 3));
+        assert_eq!(Grid::new(b"\n\n").height(), 0);
+    }
+
+    #[test]
+    fn test_invalid() {
+        assert_eq!(
+            Grid::try_new(b"1....\n..\n.....\n").unwrap_err(),
+            GridError::UnevenRow {
+                line: 2,
+                expected: 5,
+                found: 2
+            }
+        );
+        assert_eq!(
+            Grid::try_new(b"12.\n...\n\n...").unwrap_err(),
+            GridError::UnevenRow {
+                line: 3,
+                expected: 3,
+                found: 0
+            }
+        );
+        assert_eq!(
+            Grid::try_new(b"12.\r\n...\n...").unwrap_err(),
+            GridError::MixedLineEndings { line: 2 }
+        );
+        assert_eq!(
+            Grid::try_new(b"12.\n...\r\n...").unwrap_err(),
+            GridError::MixedLineEndings { line: 2 }
+        );
+    }
+
+    #[test]
+    fn test_neighbours() {
+        let grid = Grid::new(TEST);
+
+        assert_eq!(grid.neighbours4(0, 0).collect::<Vec<_>>(), [(1, 0), (0, 1)]);
+        assert_eq!(grid.neighbours4(1, 1).count(), 4);
+        assert_eq!(
+            grid.neighbours8(2, 2).collect::<Vec<_>>(),
+            [(1, 1), (2, 1), (1, 2)]
+        );
+        assert_eq!(grid.neighbours8(1, 1).count(), 8);
+    }
+}